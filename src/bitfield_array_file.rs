@@ -63,15 +63,25 @@ pub fn get_bits<P: AsRef<Path>, const BITS: usize>(
     f.seek(SeekFrom::Start(start_byte as u64)).unwrap();
     f.read(&mut buf).unwrap();
 
-    let mut bitfield = [false; BITS];
+    read_bits(&buf, (ind * BITS) % 8)
+}
+
+/// The same as `get_bits` for a whole file already read into `bytes`.
+pub fn bits_at<const BITS: usize>(bytes: &[u8], ind: usize) -> [bool; BITS] {
+    read_bits(bytes, ind * BITS)
+}
 
-    let start_bit = (ind * BITS) % 8;
+fn read_bits<const BITS: usize>(
+    bytes: &[u8],
+    start_bit: usize,
+) -> [bool; BITS] {
+    let mut bitfield = [false; BITS];
 
-    for i in 0..BITS {
+    for (i, bit) in bitfield.iter_mut().enumerate() {
         let bit_ind = start_bit + i;
         let byte_ind = bit_ind / 8;
         let sub_ind = bit_ind % 8;
-        bitfield[i] = buf[byte_ind] & 1 << sub_ind != 0;
+        *bit = bytes[byte_ind] & 1 << sub_ind != 0;
     }
 
     bitfield
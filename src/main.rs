@@ -3,32 +3,160 @@
 // #![feature(const_evaluatable_checked)]
 
 use core::panic;
-use std::env;
+use std::{env, process::exit, time::Instant};
 
 use yahtzee::DiceThrow;
+use yahtzee_distribution::{
+    check_distribution, final_score_distribution, print_distribution, Policy,
+};
+use yahtzee_free_strats::{
+    amt_cells, lookup_rounding, lookup_turn_start_value, Precision,
+};
 use yahtzee_guide::start;
+use yahtzee_objective::Objective;
+use yahtzee_player::load_strategy;
 use yahtzee_simulation::{simulate_single_game, simulate_multiple};
 use yahtzee_strats::{
-    cache_all_tables, get_yahtzee_index, load_all_tables, make_all_tables,
+    cache_all_tables, get_index_name, get_yahtzee_index, kept_dice,
+    load_all_tables, make_all_tables, ranked_rethrows, score_distribution,
+};
+use yahtzee_turn_start::{
+    bonus_threshold, cells_to_mask, turn_start_value, EndgameSolver,
 };
 
 pub mod bitfield_array_file;
 pub mod yahtzee;
+pub mod yahtzee_cheat_sheet;
+pub mod yahtzee_control;
+pub mod yahtzee_distributed;
+pub mod yahtzee_distribution;
+pub mod yahtzee_events;
+pub mod yahtzee_exact;
 pub mod yahtzee_free_strats;
 pub mod yahtzee_guide;
+pub mod yahtzee_manifest;
+pub mod yahtzee_objective;
+pub mod yahtzee_player;
 pub mod yahtzee_simulation;
 pub mod yahtzee_strats;
+pub mod yahtzee_target;
+pub mod yahtzee_tuning;
+pub mod yahtzee_turn;
+pub mod yahtzee_turn_start;
+pub mod yahtzee_versus;
 
 const HELP_MSG: &str = r#"
 commands:
 help: show this message
 compute-strats <N>: compute and cache the strats for <N> dice
+exact-strats <N>: computes the per-cell tables for <N> dice with exact
+    fractions and checks the cached floating point tables against them.
 give-best-roll <N> <cell> <throws-left> <dice>: gives the best roll for a
     the given cell and dice. Write <dice> as 314156; order does not matter.
+    Also lists every other keep ranked by expected score.
     For list of cell names run command: help-cell-names.
-guide-free-game <N>: Starts an interactive session to guide through free game
-    with <N> dice.
+cheat-sheet <N> [out-dir]: writes a printable text and html table of the
+    keep for every cell and dice, default out-dir is cheat_sheet.
+opening-book <N> [out-file]: lists the optimal keep for every first throw
+    of a game from the full lookup tables and writes them as csv, default
+    out-file is opening<N>.csv.
+compare-keeps <N> <cell> <throws-left> <dice> <keep-a> <keep-b>: shows the
+    distribution of the final score in <cell> when keeping <keep-a> or
+    <keep-b> of <dice>, written like <dice>. Use - to keep nothing.
+solve-turn <N> <cells> <throws-left> <dice>: best play of the rest of a turn
+    maximizing the score put in one of <cells>, given as a comma separated
+    list of cell names.
+solve-endgame <N> <cells> <upper-total> <throws-left> <dice>: best play and
+    expected remaining score of a position with the free <cells> given as a
+    comma separated list of cell names, solved in memory without any tables.
+    Practical up to about 8 free cells.
+guide-free-game <N> [strategy]: Starts an interactive session to guide
+    through free game with <N> dice.
+simulate-single <N> [strategy]: plays a single game and shows the sheet.
+simulate-multiple <N> <games> [strategy]: plays <games> games and shows the
+    average of each cell.
+    [strategy] is one of
+        lookup (default): the full lookup tables from compute-all-strats.
+        lookup=<objective>: the lookup tables for another objective.
+        heuristic: the per-cell strats and the cell choice heuristic.
+        turn-start: the values from compute-turn-start, with the turn itself
+            solved when asked.
+        target=<T>: maximize the chance of a total of at least <T> with the
+            tables from compute-target.
+        endgame: solves the reachable states in memory as they come up,
+            without any tables. Only practical late in a game.
+simulate-multiplayer <N> <games> <player>...: plays <games> games where the
+    players take turns, and shows how often each wins, their average rank and
+    their average total. A player is a [strategy], or win/rank to play for the
+    chance of winning or the best expected rank against the others, assuming
+    they play for the best expected score. Those need compute-turn-start and
+    compute-target, and only work with 5 dice.
+compute-all-strats init <N> [objective] [precision]: starts the full lookup
+    tables. The bonus is given once, the turn the threshold is reached, and
+    tables started when it was given again for every later cell can not be
+    resumed.
+compute-all-strats resume <N> <free-cells> <throws-left> [objective]:
+    continues the full lookup tables from the given layer.
+compute-all-strats continue <N> [objective]: continues the full lookup tables
+    from the first file that is not finished. Finished files are kept in
+    manifest.txt with their size and checksum, and missing or truncated files
+    are computed again. The files of the last finished layer and after also
+    have their checksums checked. Tables from before the manifest are
+    computed again.
+compute-all-strats work <N> [objective]: computes the full lookup tables
+    together with other worker processes, on this host or others sharing the
+    lookup directory. Each worker claims a points above of a layer at a time
+    through a lock file in locks/, and writes its own manifest. Workers keep
+    their locks fresh while working, and the coordinator gives the work of a
+    worker that stopped for 10 minutes to another.
+compute-all-strats coordinate <N> [objective]: makes the first layer and
+    verifies every layer the workers finish, by computing the checksums again
+    and comparing them with the manifest, before they start the next one.
+    Units that fail are computed again.
+    [objective] is one of
+        expected (default): the expected score.
+        exp=<θ>: exponential utility -exp(-θ * score), positive θ plays it
+            safe and negative θ takes risks.
+        meanvar=<λ>: approximates mean minus λ times the variance by
+            exponential utility with θ = 2λ, and uses the tables of exp=<2λ>.
+    [precision] is one of
+        f32 (default): 4 byte floats.
+        f64: 8 byte floats, also summed as f64.
+        u16: 2 byte fixed point in steps of 1/64 point.
+    Every objective has its own tables, with the objective and precision in
+    meta.txt. Resuming uses the precision the tables were started with.
+    While resuming, the solver listens for control commands on solver.sock.
+control <command>: sends a command to the solver running in this directory
+    and shows its reply. <command> is one of
+        pause: holds up all threads.
+        resume: runs all threads again.
+        threads <k>: runs only <k> threads.
+        stop-after-layer: stops once the current layer is done.
+        status: shows the current layer, progress and threads.
+    The hold_up and wrap_up files still work as well.
+tune-heuristic <N> <games> [rounds]: tunes the parameters of the cell choice
+    heuristic by simulating <games> games per candidate and stores the best
+    in bincode/heuristic<N>.bincode.
+compute-turn-start <N>: computes (or resumes) only the expected remaining
+    score at the start of each turn, for every set of free cells and points
+    above.
+compute-target <N>: computes (or resumes) the chance of getting at least
+    every amount of points in the rest of the game, from the start of each
+    turn. Used for playing to reach a target score.
+score-distribution <N> [strategy] [score...]: exact distribution of the
+    final score of a new game, with the chance of reaching each [score]. It
+    is played by [strategy], which may only depend on the free cells and the
+    points above, by default the full lookup tables of the expected score.
+    For those and for turn-start the mean is checked against their expected
+    score, and it exits with an error if the check fails. Only for 5 dice,
+    with 6 a layer has about 15.7 million states with hundreds of totals
+    each.
 test: current test
+
+global options, before or after any command:
+--events <target>: where <target> is stderr or the path of a file, the
+    solvers also write progress as JSON lines: layer_started, progress once
+    a second with the speed, threads and eta, and layer_finished.
 "#;
 
 pub const HELP_CELL_NAMES: &str = r#"
@@ -67,10 +195,250 @@ fn give_best_roll<const N: u64>(cell: &str, throws_left: usize, dice: &str) {
         sub_throw,
         scores[throws_left][cell_ind].get(&throw).unwrap()
     );
+
+    const TIE_EPS: f64 = 1e-9;
+
+    let ranked = ranked_rethrows(&throw, &scores[throws_left - 1][cell_ind]);
+    let best = ranked[0].1;
+
+    println!(
+        "\n{:<6}{:<8}{:<10}{:>12}{:>12}",
+        "rank", "keep", "rethrow", "expected", "loss"
+    );
+
+    for (i, (rethrow, score)) in ranked.iter().enumerate() {
+        let tie = [i.wrapping_sub(1), i + 1]
+            .iter()
+            .filter_map(|&j| ranked.get(j))
+            .any(|(_, other)| (other - score).abs() < TIE_EPS);
+
+        println!(
+            "{:<6}{:<8}{:<10}{:>12.4}{:>12.4}{}",
+            i + 1,
+            kept_dice(&throw, rethrow).digits(),
+            rethrow.digits(),
+            score,
+            best - score,
+            if tie { "  tie" } else { "" }
+        );
+    }
+}
+
+fn compare_keeps<const N: u64>(
+    cell: &str,
+    throws_left: usize,
+    dice: &str,
+    keep_a: &str,
+    keep_b: &str,
+) {
+    let throw = DiceThrow::from_digits(dice);
+
+    let keeps: Vec<_> = [keep_a, keep_b]
+        .iter()
+        .map(|keep| DiceThrow::from_digits(keep))
+        .collect();
+
+    if let Some(keep) = keeps
+        .iter()
+        .find(|keep| (1..=6).any(|i| keep[i] > throw[i]))
+    {
+        println!("Cannot keep {} from {}", keep.digits(), throw.digits());
+        return;
+    }
+
+    if throws_left == 0 {
+        println!("No throws left to rethrow with");
+        return;
+    }
+
+    let (_, strats) = load_all_tables::<N>();
+
+    let cell_ind = get_yahtzee_index::<N>(cell);
+
+    let dists: Vec<_> = keeps
+        .iter()
+        .map(|keep| {
+            score_distribution::<N>(
+                &throw,
+                &kept_dice(&throw, keep),
+                throws_left,
+                cell_ind,
+                &strats,
+            )
+        })
+        .collect();
+
+    println!("Your throw:\n{}\n", throw);
+
+    println!(
+        "{:<8}{:>14}{:>14}",
+        "score",
+        format!("keep {}", keeps[0].digits()),
+        format!("keep {}", keeps[1].digits())
+    );
+
+    let mut scores: Vec<_> =
+        dists.iter().flat_map(|dist| dist.keys().cloned()).collect();
+    scores.sort_unstable();
+    scores.dedup();
+
+    for score in scores {
+        println!(
+            "{:<8}{:>14.6}{:>14.6}",
+            score,
+            dists[0].get(&score).cloned().unwrap_or(0.0),
+            dists[1].get(&score).cloned().unwrap_or(0.0)
+        );
+    }
+
+    let means: Vec<_> = dists
+        .iter()
+        .map(|dist| dist.iter().map(|(&s, p)| s as f64 * p).sum::<f64>())
+        .collect();
+
+    println!("{:<8}{:>14.6}{:>14.6}", "mean", means[0], means[1]);
+}
+
+fn solve_turn<const N: u64>(cells: &str, throws_left: usize, dice: &str) {
+    let mut free_cells = vec![false; amt_cells::<N>()];
+    for cell in cells.split(',') {
+        free_cells[get_yahtzee_index::<N>(cell)] = true;
+    }
+
+    let throw = DiceThrow::from_digits(dice);
+
+    let solution =
+        yahtzee_turn::solve_turn::<N, _>(&free_cells, |_, score| score as f64);
+
+    println!("Your throw:\n{}\n", throw);
+
+    if throws_left == 0 {
+        let cell_ind = solution.cell::<N>(&throw);
+        println!(
+            "Put {} points in {}.",
+            throw.cell_score::<N>(cell_ind),
+            get_index_name::<N>(cell_ind)
+        );
+    } else {
+        println!(
+            "Rethrow:\n{}\nwith expected score of: {}",
+            solution.rethrow::<N>(&throw, throws_left),
+            solution.value::<N>(&throw, throws_left)
+        );
+    }
+}
+
+fn solve_endgame<const N: u64>(
+    cells: &str,
+    points_above: u64,
+    throws_left: usize,
+    dice: &str,
+) {
+    let mut free_cells = vec![false; amt_cells::<N>()];
+    for cell in cells.split(',') {
+        free_cells[get_yahtzee_index::<N>(cell)] = true;
+    }
+
+    let throw = DiceThrow::from_digits(dice);
+
+    let solver = EndgameSolver::<N>::new();
+
+    let timer = Instant::now();
+    let solution = solver.solve_turn(
+        cells_to_mask(&free_cells),
+        points_above.min(bonus_threshold::<N>()),
+    );
+    println!(
+        "Solved {} turn starts in {:?}",
+        solver.amt_solved() + 1,
+        timer.elapsed()
+    );
+
+    println!("Your throw:\n{}\n", throw);
+
+    if throws_left == 0 {
+        let cell_ind = solution.cell::<N>(&throw);
+        println!(
+            "Put {} points in {}.",
+            throw.cell_score::<N>(cell_ind),
+            get_index_name::<N>(cell_ind)
+        );
+    } else {
+        println!("Rethrow:\n{}", solution.rethrow::<N>(&throw, throws_left));
+    }
+
+    println!(
+        "Expected remaining score: {}",
+        solution.value::<N>(&throw, throws_left)
+    );
+}
+
+fn final_distribution<const N: u64>(strategy: Option<&str>, scores: &[usize]) {
+    let points = vec![None; amt_cells::<N>()];
+    let policy = Policy::parse::<N>(strategy);
+
+    let timer = Instant::now();
+    let dist = final_score_distribution::<N>(&policy, &points, None);
+    println!("took {:?}", timer.elapsed());
+
+    let cells = vec![true; amt_cells::<N>()];
+    let check = match &policy {
+        Policy::Lookup(Objective::Expected) => check_distribution(
+            &dist,
+            lookup_turn_start_value::<N>(Objective::Expected, &cells, 0),
+            1e-2 + lookup_rounding::<N>(Objective::Expected),
+        ),
+        Policy::BestExpected(values) => check_distribution(
+            &dist,
+            turn_start_value::<N>(values, &cells, 0) as f64,
+            1e-2,
+        ),
+        _ => Ok(()),
+    };
+
+    print_distribution::<N>(&dist, scores);
+
+    if let Err(message) = check {
+        eprintln!("{}", message);
+        exit(1);
+    }
+}
+
+fn parse_objective(name: Option<&String>) -> Objective {
+    name.map_or(Objective::Expected, |name| {
+        Objective::parse(name).expect("Unknown objective")
+    })
+}
+
+fn parse_tree_options(options: &[String]) -> (Objective, Precision) {
+    let mut objective = Objective::Expected;
+    let mut precision = Precision::F32;
+
+    for option in options {
+        if let Some(p) = Precision::parse(option) {
+            precision = p;
+        } else {
+            objective = Objective::parse(option).expect("Unknown objective");
+        }
+    }
+
+    (objective, precision)
 }
 
 fn main() {
-    let args: Vec<_> = env::args().collect();
+    let mut args: Vec<_> = env::args().collect();
+
+    // Any command may write events, so the option is taken out before the
+    // arguments of the command are read.
+    if let Some(i) = args.iter().position(|x| x == "--events") {
+        let target = args
+            .get(i + 1)
+            .expect("Must give where to write the events!")
+            .clone();
+        yahtzee_events::set_events_target(&target);
+        args.drain(i..=i + 1);
+    }
+
     let refargs: Vec<_> = args.iter().map(|x| x.as_str()).collect();
 
     if let Some(command) = args.get(1) {
@@ -84,6 +452,11 @@ fn main() {
                     _ => unimplemented!("Invalid number of dice!"),
                 }
             }
+            "exact-strats" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => yahtzee_exact::check_tables::<5>(),
+                Some("6") => yahtzee_exact::check_tables::<6>(),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
             "give-best-roll" => {
                 match &args.get(2).and_then(|x| Some(x.as_str())) {
                     Some("5") => give_best_roll::<5>(
@@ -100,11 +473,78 @@ fn main() {
                     _ => unimplemented!("Invalid number of dice!"),
                 }
             }
+            "cheat-sheet" => {
+                let out_dir = args.get(3).map_or("cheat_sheet", |x| x.as_str());
+                match args.get(2).map(|x| x.as_str()) {
+                    Some("5") => {
+                        yahtzee_cheat_sheet::make_cheat_sheet::<5>(out_dir)
+                    }
+                    Some("6") => {
+                        yahtzee_cheat_sheet::make_cheat_sheet::<6>(out_dir)
+                    }
+                    _ => panic!("Must give number of dice (5/6)!"),
+                }
+            }
+            "opening-book" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => yahtzee_free_strats::opening_book::<5>(
+                    args.get(3).map_or("opening5.csv", |x| x.as_str()),
+                ),
+                Some("6") => yahtzee_free_strats::opening_book::<6>(
+                    args.get(3).map_or("opening6.csv", |x| x.as_str()),
+                ),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
+            "compare-keeps" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => compare_keeps::<5>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    &args[5],
+                    &args[6],
+                    &args[7],
+                ),
+                Some("6") => compare_keeps::<6>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    &args[5],
+                    &args[6],
+                    &args[7],
+                ),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
+            "solve-turn" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => solve_turn::<5>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    &args[5],
+                ),
+                Some("6") => solve_turn::<6>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    &args[5],
+                ),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
+            "solve-endgame" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => solve_endgame::<5>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    args[5].parse().unwrap(),
+                    &args[6],
+                ),
+                Some("6") => solve_endgame::<6>(
+                    &args[3],
+                    args[4].parse().unwrap(),
+                    args[5].parse().unwrap(),
+                    &args[6],
+                ),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
             "help-cell-names" => println!("{}", HELP_CELL_NAMES),
             "guide-free-game" => {
+                let strategy = args.get(3).map(|x| x.as_str());
                 match &args.get(2).and_then(|x| Some(x.as_str())) {
-                    Some("5") => start::<5>(),
-                    Some("6") => start::<6>(),
+                    Some("5") => start::<5>(&*load_strategy::<5>(strategy)),
+                    Some("6") => start::<6>(&*load_strategy::<6>(strategy)),
                     None => panic!("Must give number of dice (5/6)!"),
                     _ => unimplemented!("Invalid number of dice!"),
                 }
@@ -113,36 +553,138 @@ fn main() {
                 if let Some(command) = args.get(2) {
                     match command.as_str() {
                         "test" => yahtzee_free_strats::test(&refargs[3..]),
-                        "init" => yahtzee_free_strats::init(&args[3]),
-                        "resume" => match args[3].as_str() {
-                            "5" => yahtzee_free_strats::resume_calcs_5(
-                                args[4].parse().unwrap(),
-                                args[5].parse().unwrap(),
-                            ),
-                            "6" => yahtzee_free_strats::resume_calcs_6(
-                                args[4].parse().unwrap(),
-                                args[5].parse().unwrap(),
-                            ),
-                            _ => unreachable!(),
-                        },
+                        "init" => {
+                            let (objective, precision) =
+                                parse_tree_options(&args[4..]);
+                            yahtzee_free_strats::init(
+                                &args[3], objective, precision,
+                            )
+                        }
+                        "resume" => {
+                            match args[3].as_str() {
+                                "5" => yahtzee_free_strats::resume_calcs_5(
+                                    args[4].parse().unwrap(),
+                                    args[5].parse().unwrap(),
+                                    parse_objective(args.get(6)),
+                                ),
+                                "6" => yahtzee_free_strats::resume_calcs_6(
+                                    args[4].parse().unwrap(),
+                                    args[5].parse().unwrap(),
+                                    parse_objective(args.get(6)),
+                                ),
+                                _ => unreachable!(),
+                            }
+                            yahtzee_control::stop_control_socket();
+                        }
+                        "work" => yahtzee_free_strats::work(
+                            &args[3],
+                            parse_objective(args.get(4)),
+                        ),
+                        "coordinate" => yahtzee_free_strats::coordinate(
+                            &args[3],
+                            parse_objective(args.get(4)),
+                        ),
+                        "continue" => {
+                            yahtzee_free_strats::continue_calcs(
+                                &args[3],
+                                parse_objective(args.get(4)),
+                            );
+                            yahtzee_control::stop_control_socket();
+                        }
                         _ => println!("invalid command"),
                     }
                 } else {
                     println!("Give command");
                 }
             }
+            "control" => yahtzee_control::send_command(&refargs[2..].join(" ")),
             "simulate-single" => {
+                let strategy = args.get(3).map(|x| x.as_str());
                 match &args.get(2).and_then(|x| Some(x.as_str())) {
-                    Some("5") => simulate_single_game::<5>(),
-                    Some("6") => simulate_single_game::<6>(),
+                    Some("5") => simulate_single_game::<5, _>(
+                        &*load_strategy::<5>(strategy),
+                    ),
+                    Some("6") => simulate_single_game::<6, _>(
+                        &*load_strategy::<6>(strategy),
+                    ),
                     _ => panic!("Must give number of dice (5/6)!"),
                 }
             }
+            "simulate-multiplayer" => {
+                let n = args[3].parse().unwrap();
+                let result = match args.get(2).map(|x| x.as_str()) {
+                    Some("5") => yahtzee_versus::simulate_multiplayer::<5>(
+                        &refargs[4..],
+                        n,
+                    ),
+                    Some("6") => yahtzee_versus::simulate_multiplayer::<6>(
+                        &refargs[4..],
+                        n,
+                    ),
+                    _ => panic!("Must give number of dice (5/6)!"),
+                };
+
+                if let Err(message) = result {
+                    eprintln!("{}", message);
+                    exit(1);
+                }
+            }
             "simulate-multiple" => {
                 let n = args[3].parse().unwrap();
+                let strategy = args.get(4).map(|x| x.as_str());
                 match &args.get(2).and_then(|x| Some(x.as_str())) {
-                    Some("5") => simulate_multiple::<5>(n),
-                    Some("6") => simulate_multiple::<6>(n),
+                    Some("5") => simulate_multiple::<5, _>(
+                        &*load_strategy::<5>(strategy),
+                        n,
+                    ),
+                    Some("6") => simulate_multiple::<6, _>(
+                        &*load_strategy::<6>(strategy),
+                        n,
+                    ),
+                    _ => panic!("Must give number of dice (5/6)!"),
+                }
+            }
+            "compute-turn-start" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => {
+                    yahtzee_turn_start::compute_turn_start_values::<5>()
+                }
+                Some("6") => {
+                    yahtzee_turn_start::compute_turn_start_values::<6>()
+                }
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
+            "compute-target" => match args.get(2).map(|x| x.as_str()) {
+                Some("5") => yahtzee_target::compute_target_tables::<5>(),
+                Some("6") => yahtzee_target::compute_target_tables::<6>(),
+                _ => panic!("Must give number of dice (5/6)!"),
+            },
+            "score-distribution" => {
+                let strategy = args
+                    .get(3)
+                    .filter(|x| x.parse::<usize>().is_err())
+                    .map(|x| x.as_str());
+                let scores: Vec<_> = args[3 + strategy.is_some() as usize..]
+                    .iter()
+                    .map(|x| x.parse().unwrap())
+                    .collect();
+                match args.get(2).map(|x| x.as_str()) {
+                    Some("5") => final_distribution::<5>(strategy, &scores),
+                    Some("6") => {
+                        eprintln!(
+                            "Score distributions are only practical for 5 \
+                             dice!"
+                        );
+                        exit(1);
+                    }
+                    _ => panic!("Must give number of dice (5/6)!"),
+                }
+            }
+            "tune-heuristic" => {
+                let games = args[3].parse().unwrap();
+                let rounds = args.get(4).map_or(20, |x| x.parse().unwrap());
+                match args.get(2).map(|x| x.as_str()) {
+                    Some("5") => yahtzee_tuning::tune::<5>(games, rounds),
+                    Some("6") => yahtzee_tuning::tune::<6>(games, rounds),
                     _ => panic!("Must give number of dice (5/6)!"),
                 }
             }
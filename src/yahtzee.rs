@@ -81,9 +81,11 @@ impl DiceThrow {
     }
 
     pub fn throw(n: usize) -> Self {
-        let mut dice_throw = Self::new();
+        Self::throw_with(n, &mut rand::thread_rng())
+    }
 
-        let mut rng = rand::thread_rng();
+    pub fn throw_with<R: Rng>(n: usize, rng: &mut R) -> Self {
+        let mut dice_throw = Self::new();
 
         for _ in 0..n {
             let eyes = rng.gen_range(1..=6);
@@ -221,6 +223,12 @@ impl DiceThrow {
     }
 
     pub fn probability(&self) -> f64 {
+        let (actual_perms, tot) = self.probability_ratio();
+
+        (actual_perms as f64) / (tot as f64)
+    }
+
+    pub fn probability_ratio(&self) -> (u64, u64) {
         let amt_dice = self.amt_dice();
 
         let tot = 6u64.pow(amt_dice as u32);
@@ -229,9 +237,7 @@ impl DiceThrow {
 
         let dup_perms: u64 = (1..=6).map(|i| factorial(self[i])).product();
 
-        let actual_perms = perms / dup_perms;
-
-        (actual_perms as f64) / (tot as f64)
+        (perms / dup_perms, tot)
     }
 
     pub fn into_ordered_dice<'a>(&'a self) -> impl Iterator<Item = u64> + 'a {
@@ -240,6 +246,27 @@ impl DiceThrow {
             .enumerate()
             .flat_map(|(i, &amt)| (0..amt).map(move |_| (i as u64) + 1))
     }
+
+    pub fn from_digits(digits: &str) -> Self {
+        let mut dice = Self::new();
+        for c in digits.chars().filter(|c| ('1'..='6').contains(c)) {
+            dice[(c as u8 - b'0') as u64] += 1;
+        }
+        dice
+    }
+
+    pub fn digits(&self) -> String {
+        let s: String = self
+            .into_ordered_dice()
+            .map(|x| (b'0' + x as u8) as char)
+            .collect();
+
+        if s.is_empty() {
+            "-".to_owned()
+        } else {
+            s
+        }
+    }
 }
 
 fn factorial(n: u64) -> u64 {
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as FmtWrite,
+    fs::{create_dir_all, write},
+    path::Path,
+};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_strats::{get_index_name, kept_dice, load_all_tables, DiceIter},
+};
+
+struct KeepGroup {
+    keep: DiceThrow,
+    score: f64,
+    dice: Vec<DiceThrow>,
+}
+
+// The expected score only depends on what is kept, so every dice state that
+// keeps the same dice ends up in the same row.
+fn make_groups(
+    n: u64,
+    strat: &HashMap<DiceThrow, DiceThrow>,
+    scores: &HashMap<DiceThrow, f64>,
+) -> Vec<KeepGroup> {
+    let mut groups: Vec<KeepGroup> = Vec::new();
+    let mut group_inds = HashMap::new();
+
+    for dice in DiceIter::new(n) {
+        let keep = kept_dice(&dice, strat.get(&dice).unwrap());
+        let score = *scores.get(&dice).unwrap();
+
+        let &mut ind = group_inds.entry(keep.clone()).or_insert_with(|| {
+            groups.push(KeepGroup {
+                keep,
+                score,
+                dice: Vec::new(),
+            });
+            groups.len() - 1
+        });
+
+        groups[ind].dice.push(dice);
+    }
+
+    groups.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    groups
+}
+
+fn section_title<const N: u64>(cell_ind: usize, throws_left: usize) -> String {
+    format!(
+        "{}, {} rethrow{} left",
+        get_index_name::<N>(cell_ind),
+        throws_left,
+        if throws_left == 1 { "" } else { "s" }
+    )
+}
+
+fn write_text<const N: u64>(
+    out: &mut String,
+    cell_ind: usize,
+    throws_left: usize,
+    groups: &[KeepGroup],
+) {
+    const WIDTH: usize = 80;
+    const INDENT: usize = 18;
+
+    writeln!(out, "== {} ==", section_title::<N>(cell_ind, throws_left))
+        .unwrap();
+    writeln!(out, "keep       score  dice").unwrap();
+
+    for group in groups {
+        let mut line =
+            format!("{:<8}{:>8.3}  ", group.keep.digits(), group.score);
+
+        for dice in &group.dice {
+            let s = dice.digits();
+            if line.len() + s.len() + 1 > WIDTH {
+                writeln!(out, "{}", line.trim_end()).unwrap();
+                line = " ".repeat(INDENT);
+            }
+            line.push_str(&s);
+            line.push(' ');
+        }
+
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+
+    writeln!(out).unwrap();
+}
+
+fn write_html<const N: u64>(
+    out: &mut String,
+    cell_ind: usize,
+    throws_left: usize,
+    groups: &[KeepGroup],
+) {
+    writeln!(out, "<section>").unwrap();
+    writeln!(
+        out,
+        "<h2>{}</h2>",
+        section_title::<N>(cell_ind, throws_left)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<table><tr><th>keep</th><th>score</th><th>dice</th></tr>"
+    )
+    .unwrap();
+
+    for group in groups {
+        let dice: Vec<_> = group.dice.iter().map(|x| x.digits()).collect();
+
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{:.3}</td><td>{}</td></tr>",
+            group.keep.digits(),
+            group.score,
+            dice.join(" ")
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "</table>").unwrap();
+    writeln!(out, "</section>").unwrap();
+}
+
+pub fn make_cheat_sheet<const N: u64>(out_dir: &str) {
+    let (scores, strats) = load_all_tables::<N>();
+
+    let mut text = String::new();
+    let mut html = String::new();
+
+    writeln!(
+        html,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Yahtzee keeps for {} dice</title>
+<style>
+body {{ font-family: monospace; font-size: 8pt; columns: 2; }}
+section {{ break-inside: avoid; }}
+h2 {{ font-size: 10pt; margin: 8pt 0 2pt 0; }}
+table {{ border-collapse: collapse; }}
+td, th {{ border: 1px solid #999; padding: 0 3pt; vertical-align: top; }}
+</style>
+</head>
+<body>"#,
+        N
+    )
+    .unwrap();
+
+    for cell_ind in 0..scores[0].len() {
+        for throws_left in (1..=2).rev() {
+            let groups = make_groups(
+                N,
+                &strats[throws_left][cell_ind],
+                &scores[throws_left][cell_ind],
+            );
+
+            write_text::<N>(&mut text, cell_ind, throws_left, &groups);
+            write_html::<N>(&mut html, cell_ind, throws_left, &groups);
+        }
+    }
+
+    writeln!(html, "</body>\n</html>").unwrap();
+
+    let out_dir = Path::new(out_dir);
+    create_dir_all(out_dir).unwrap();
+
+    let text_path = out_dir.join(format!("keeps{}.txt", N));
+    let html_path = out_dir.join(format!("keeps{}.html", N));
+
+    write(&text_path, text).unwrap();
+    write(&html_path, html).unwrap();
+
+    println!(
+        "Wrote {} and {}",
+        text_path.to_string_lossy(),
+        html_path.to_string_lossy()
+    );
+}
@@ -0,0 +1,192 @@
+use std::{path::Path, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::yahtzee_free_strats::NUM_CPUS;
+
+#[cfg(unix)]
+pub use socket::{send_command, start_control_socket, stop_control_socket};
+
+#[cfg(not(unix))]
+pub use no_socket::{send_command, start_control_socket, stop_control_socket};
+
+/// The socket in the working directory that a running solver listens on.
+pub const SOCKET_PATH: &str = "solver.sock";
+
+struct ControlState {
+    held: usize,
+    stop_after_layer: bool,
+    layer: String,
+    progress: String,
+}
+
+static CONTROL: Lazy<Mutex<ControlState>> = Lazy::new(|| {
+    Mutex::new(ControlState {
+        held: 0,
+        stop_after_layer: false,
+        layer: String::new(),
+        progress: String::new(),
+    })
+});
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn handle_command(command: &str) -> String {
+    let mut control = CONTROL.lock().unwrap();
+
+    match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["pause"] => {
+            control.held = *NUM_CPUS;
+            "ok: pausing all threads\n".to_owned()
+        }
+        ["resume"] => {
+            control.held = 0;
+            format!("ok: running all {} threads\n", *NUM_CPUS)
+        }
+        ["threads", k] => match k.parse::<usize>() {
+            Ok(k) if k <= *NUM_CPUS => {
+                control.held = *NUM_CPUS - k;
+                format!("ok: running {} of {} threads\n", k, *NUM_CPUS)
+            }
+            _ => format!("error: give a thread count up to {}\n", *NUM_CPUS),
+        },
+        ["stop-after-layer"] => {
+            control.stop_after_layer = true;
+            format!("ok: stopping after {}\n", control.layer)
+        }
+        ["status"] => format!(
+            "layer: {}\nprogress: {}\nthreads: {} of {} running\n\
+            stop after layer: {}\n",
+            control.layer,
+            control.progress,
+            *NUM_CPUS - control.held,
+            *NUM_CPUS,
+            if control.stop_after_layer {
+                "yes"
+            } else {
+                "no"
+            }
+        ),
+        _ => format!("error: unknown command {:?}\n", command),
+    }
+}
+
+/// Amount of worker threads to hold up. The hold_up file, holding an optional
+/// amount, still takes precedence over the socket.
+pub fn held_threads() -> usize {
+    if Path::new("hold_up").exists() {
+        std::fs::read_to_string("hold_up")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap_or(*NUM_CPUS)
+    } else {
+        CONTROL.lock().unwrap().held
+    }
+}
+
+/// Whether to stop once the current layer is done, from the socket or the
+/// wrap_up file.
+pub fn stop_requested() -> bool {
+    Path::new("wrap_up").exists() || CONTROL.lock().unwrap().stop_after_layer
+}
+
+pub fn set_layer(layer: String) {
+    CONTROL.lock().unwrap().layer = layer;
+}
+
+pub fn set_progress(progress: String) {
+    CONTROL.lock().unwrap().progress = progress;
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::{
+        io::{BufRead, BufReader, ErrorKind, Read, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        path::Path,
+        sync::atomic::{AtomicBool, Ordering},
+        thread::spawn,
+    };
+
+    use once_cell::sync::Lazy;
+
+    use super::{handle_command, SOCKET_PATH};
+
+    // Whether this process listens on `SOCKET_PATH`, and so removes it again.
+    static OWNS_SOCKET: AtomicBool = AtomicBool::new(false);
+
+    static LISTENER: Lazy<()> = Lazy::new(|| {
+        // A socket nobody listens on is left by a solver that was killed.
+        if Path::new(SOCKET_PATH).exists() {
+            match UnixStream::connect(SOCKET_PATH) {
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(SOCKET_PATH).unwrap()
+                }
+                _ => {
+                    println!(
+                        "Another solver is listening on {}, \
+                        not taking commands",
+                        SOCKET_PATH
+                    );
+                    return;
+                }
+            }
+        }
+
+        let listener = UnixListener::bind(SOCKET_PATH).unwrap();
+        OWNS_SOCKET.store(true, Ordering::SeqCst);
+
+        spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut line = String::new();
+                let mut reader = BufReader::new(&stream);
+                if reader.read_line(&mut line).is_err() {
+                    continue;
+                }
+
+                let reply = handle_command(line.trim());
+                let _ = (&stream).write_all(reply.as_bytes());
+            }
+        });
+    });
+
+    /// Starts listening for commands on `SOCKET_PATH`, if not already.
+    pub fn start_control_socket() {
+        Lazy::force(&LISTENER);
+    }
+
+    pub fn stop_control_socket() {
+        if OWNS_SOCKET.swap(false, Ordering::SeqCst)
+            && Path::new(SOCKET_PATH).exists()
+        {
+            std::fs::remove_file(SOCKET_PATH).unwrap();
+        }
+    }
+
+    /// Sends `command` to the solver running in the working directory and
+    /// prints the reply.
+    pub fn send_command(command: &str) {
+        let mut stream = UnixStream::connect(SOCKET_PATH)
+            .expect("No solver is listening in this directory!");
+
+        writeln!(stream, "{}", command).unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+
+        print!("{}", reply);
+    }
+}
+
+// Without Unix sockets the solver is only controlled through the hold_up and
+// wrap_up files.
+#[cfg(not(unix))]
+mod no_socket {
+    pub fn start_control_socket() {}
+
+    pub fn stop_control_socket() {}
+
+    pub fn send_command(_command: &str) {
+        println!("The control socket needs a Unix system");
+    }
+}
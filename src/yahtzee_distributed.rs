@@ -0,0 +1,173 @@
+use std::{
+    collections::HashSet,
+    fs::{
+        create_dir_all, read_to_string, remove_dir_all, remove_file, rename,
+        write, OpenOptions,
+    },
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread::{sleep, spawn},
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+
+// Work units of a tree are claimed by creating locks/<layer>/<points_above>.lock
+// in the tree, which only one process can do even across hosts sharing the
+// directory. The lock stays once the unit is done. While a worker is on a
+// unit it rewrites the lock every `HEARTBEAT`, so a lock of an unfinished unit
+// that is older than `STALE_AFTER` belongs to a worker that stopped, and the
+// coordinator gives the unit back. A worker only refreshes a lock that still
+// holds its id, so one that was given to another worker is left alone, and its
+// results are thrown away. A layer is verified once locks/<layer>.verified
+// exists.
+
+const HEARTBEAT: Duration = Duration::from_secs(60);
+
+pub const STALE_AFTER: Duration = Duration::from_secs(600);
+
+/// Identifies this process among the workers, as host, process id and a
+/// random part, since hosts in containers may share a name.
+pub static WORKER_ID: Lazy<String> = Lazy::new(|| {
+    let host = read_to_string("/etc/hostname")
+        .map(|x| x.trim().to_owned())
+        .unwrap_or_else(|_| "localhost".to_owned());
+
+    format!(
+        "{}-{}-{:08x}",
+        host,
+        std::process::id(),
+        rand::random::<u32>()
+    )
+});
+
+// Locks of the units this process is working on.
+static HELD: Lazy<Mutex<HashSet<PathBuf>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+static WORKER: AtomicBool = AtomicBool::new(false);
+
+/// Makes this process claim every unit it works on, and record its files in a
+/// manifest of its own.
+pub fn become_worker() {
+    if WORKER.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    spawn(|| loop {
+        sleep(HEARTBEAT);
+        HELD.lock()
+            .unwrap()
+            .retain(|path| owns_lock(path) && refresh_lock(path));
+    });
+}
+
+fn owns_lock(path: &Path) -> bool {
+    matches!(read_to_string(path), Ok(id) if id == *WORKER_ID)
+}
+
+// Touches the lock without creating it again if it was removed meanwhile.
+fn refresh_lock(path: &Path) -> bool {
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+        .is_ok()
+}
+
+pub fn is_worker() -> bool {
+    WORKER.load(Ordering::SeqCst)
+}
+
+fn lock_path(tree: &Path, layer: &str, points_above: usize) -> PathBuf {
+    tree.join(format!("locks/{}/{}.lock", layer, points_above))
+}
+
+fn verified_path(tree: &Path, layer: &str) -> PathBuf {
+    tree.join(format!("locks/{}.verified", layer))
+}
+
+/// Claims the unit for this process, or returns false if another process
+/// already has.
+pub fn claim_unit(tree: &Path, layer: &str, points_above: usize) -> bool {
+    let path = lock_path(tree, layer, points_above);
+    create_dir_all(path.parent().unwrap()).unwrap();
+
+    match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(mut file) => {
+            file.write_all(WORKER_ID.as_bytes()).unwrap();
+            HELD.lock().unwrap().insert(path);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Stops the heartbeat of a unit this process is done with, and returns
+/// whether the unit is still its own. If not, it was given to another worker
+/// and the results must be thrown away.
+pub fn finish_unit(tree: &Path, layer: &str, points_above: usize) -> bool {
+    let path = lock_path(tree, layer, points_above);
+
+    HELD.lock().unwrap().remove(&path) && owns_lock(&path)
+}
+
+/// Whether the lock of the unit has missed its heartbeats for `STALE_AFTER`.
+pub fn is_stale(tree: &Path, layer: &str, points_above: usize) -> bool {
+    match lock_path(tree, layer, points_above)
+        .metadata()
+        .and_then(|m| m.modified())
+    {
+        Ok(modified) => {
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default()
+                > STALE_AFTER
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn is_claimed(tree: &Path, layer: &str, points_above: usize) -> bool {
+    lock_path(tree, layer, points_above).exists()
+}
+
+pub fn release_unit(tree: &Path, layer: &str, points_above: usize) {
+    let path = lock_path(tree, layer, points_above);
+    if path.exists() {
+        remove_file(path).unwrap();
+    }
+}
+
+pub fn is_verified(tree: &Path, layer: &str) -> bool {
+    verified_path(tree, layer).exists()
+}
+
+pub fn mark_verified(tree: &Path, layer: &str) {
+    create_dir_all(tree.join("locks")).unwrap();
+    write(verified_path(tree, layer), WORKER_ID.as_bytes()).unwrap();
+}
+
+pub fn clear_locks(tree: &Path) {
+    if tree.join("locks").exists() {
+        remove_dir_all(tree.join("locks")).unwrap();
+    }
+}
+
+/// Where this process writes `path` before it is finished, so no other
+/// process sees a partly written file.
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap().to_owned();
+    name.push(format!(".part-{}", *WORKER_ID));
+
+    path.with_file_name(name)
+}
+
+/// Moves the finished file written to `part_path(path)` in place.
+pub fn finish_part(path: &Path) {
+    rename(part_path(path), path).unwrap();
+}
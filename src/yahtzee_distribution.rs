@@ -0,0 +1,476 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc, thread::spawn};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_free_strats::{amt_cells, LookupTurns, NUM_CPUS},
+    yahtzee_guide::get_total_score,
+    yahtzee_objective::Objective,
+    yahtzee_player::load_strategy,
+    yahtzee_strats::{get_dice_index, get_rethrow_table, DiceIter},
+    yahtzee_turn::TurnSolution,
+    yahtzee_turn_start::{
+        bonus_threshold, cells_to_mask, fill_cell, load_turn_start_values,
+        mask_to_cells, solve_turn_from_values,
+    },
+};
+
+/// How the turns are played.
+#[derive(Clone)]
+pub enum Policy {
+    /// By the lookup tables of the objective, read a unit of the tables at a
+    /// time.
+    Lookup(Objective),
+    /// For the best expected score, by the turn start values.
+    BestExpected(Arc<Vec<f32>>),
+    /// By the strategy with this name, which each thread loads for itself.
+    /// Its choices may only depend on the free cells and the points above.
+    Strategy(String),
+}
+
+impl Policy {
+    /// The policy of a strategy name, by the lookup tables of the expected
+    /// score if none is given. The lookup tables and turn-start are read
+    /// whole instead of through their strategies.
+    pub fn parse<const N: u64>(name: Option<&str>) -> Policy {
+        match name {
+            None | Some("lookup") => Policy::Lookup(Objective::Expected),
+            Some(name) if name.starts_with("lookup=") => Policy::Lookup(
+                Objective::parse(&name["lookup=".len()..])
+                    .expect("Unknown objective"),
+            ),
+            Some("turn-start") => {
+                Policy::BestExpected(Arc::new(load_turn_start_values::<N>()))
+            }
+            Some(name) => Policy::Strategy(name.to_owned()),
+        }
+    }
+}
+
+// Points of a sheet with the cells in `mask` free and `points_above` reached,
+// all in the first filled upper cell.
+fn state_points<const N: u64>(
+    mask: usize,
+    points_above: u64,
+) -> Vec<Option<u64>> {
+    let mut points: Vec<_> = (0..amt_cells::<N>())
+        .map(|i| if mask & (1 << i) != 0 { None } else { Some(0) })
+        .collect();
+
+    if let Some(cell) = points.iter_mut().take(6).find(|x| x.is_some()) {
+        *cell = Some(points_above);
+    }
+
+    points
+}
+
+fn turn_solver<const N: u64>(
+    policy: &Policy,
+) -> Box<dyn Fn(usize, u64) -> TurnSolution> {
+    match policy {
+        Policy::Lookup(objective) => {
+            let objective = *objective;
+            let turns: RefCell<Option<LookupTurns<N>>> = RefCell::new(None);
+            Box::new(move |mask, points_above| {
+                let free_cells = mask.count_ones() as usize;
+                let points_above = points_above as usize;
+
+                let mut turns = turns.borrow_mut();
+                if !matches!(&*turns, Some(t) if t.free_cells == free_cells
+                    && t.points_above == points_above)
+                {
+                    *turns = Some(LookupTurns::load(
+                        objective,
+                        free_cells,
+                        points_above,
+                    ));
+                }
+
+                turns
+                    .as_ref()
+                    .unwrap()
+                    .turn_solution(&mask_to_cells::<N>(mask))
+            })
+        }
+        Policy::BestExpected(values) => {
+            let values = values.clone();
+            Box::new(move |mask, points_above| {
+                solve_turn_from_values::<N>(&values, mask, points_above)
+            })
+        }
+        Policy::Strategy(name) => {
+            let strategy = load_strategy::<N>(Some(name));
+            Box::new(move |mask, points_above| {
+                let points = state_points::<N>(mask, points_above);
+
+                let rethrows = (0..=2)
+                    .map(|throws_left| {
+                        if throws_left == 0 {
+                            return Vec::new();
+                        }
+                        DiceIter::new(N)
+                            .map(|dice| {
+                                strategy.rethrow(&points, &dice, throws_left)
+                            })
+                            .collect()
+                    })
+                    .collect();
+
+                TurnSolution {
+                    values: Vec::new(),
+                    rethrows,
+                    cells: DiceIter::new(N)
+                        .map(|dice| strategy.cell(&points, &dice))
+                        .collect(),
+                }
+            })
+        }
+    }
+}
+
+/// Probability of each final total, indexed by the total.
+pub struct ScoreDistribution {
+    pub probs: Vec<f64>,
+}
+
+impl ScoreDistribution {
+    pub fn mean(&self) -> f64 {
+        self.probs
+            .iter()
+            .enumerate()
+            .map(|(score, p)| score as f64 * p)
+            .sum()
+    }
+
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+
+        self.probs
+            .iter()
+            .enumerate()
+            .map(|(score, p)| (score as f64 - mean).powi(2) * p)
+            .sum()
+    }
+
+    /// Lowest score that is reached with at most probability `1 - p`.
+    pub fn percentile(&self, p: f64) -> usize {
+        let mut acc = 0.0;
+
+        for (score, prob) in self.probs.iter().enumerate() {
+            acc += prob;
+            if acc >= p {
+                return score;
+            }
+        }
+
+        self.probs.len() - 1
+    }
+
+    pub fn at_least(&self, score: usize) -> f64 {
+        self.probs.iter().skip(score).fold(0.0, |acc, p| acc + p)
+    }
+
+    pub fn total(&self) -> f64 {
+        self.probs.iter().sum()
+    }
+
+    pub fn prob(&self, score: usize) -> f64 {
+        self.probs.get(score).copied().unwrap_or(0.0)
+    }
+
+    /// The same distribution with `amount` points added to every total.
+    pub fn shifted(&self, amount: usize) -> ScoreDistribution {
+        let mut probs = vec![0.0; amount];
+        probs.extend_from_slice(&self.probs);
+
+        ScoreDistribution { probs }
+    }
+}
+
+// Probabilities of the totals from `start` and up.
+#[derive(Clone)]
+struct Partial {
+    start: usize,
+    probs: Vec<f64>,
+}
+
+impl Partial {
+    fn new() -> Self {
+        Self {
+            start: usize::MAX,
+            probs: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, other: &Partial, shift: usize, weight: f64) {
+        let other_start = other.start + shift;
+
+        if self.probs.is_empty() {
+            self.start = other_start;
+        } else if other_start < self.start {
+            let mut probs = vec![0.0; self.start - other_start];
+            probs.append(&mut self.probs);
+            self.probs = probs;
+            self.start = other_start;
+        }
+
+        let offset = other_start - self.start;
+        if self.probs.len() < offset + other.probs.len() {
+            self.probs.resize(offset + other.probs.len(), 0.0);
+        }
+
+        for (i, p) in other.probs.iter().enumerate() {
+            self.probs[offset + i] += p * weight;
+        }
+    }
+}
+
+/// Probability of each (cell, score) that a turn played by `solution` ends
+/// in, from `dice` with some throws left or else from the first throw.
+pub fn turn_outcomes<const N: u64>(
+    solution: &TurnSolution,
+    dice: Option<(&DiceThrow, usize)>,
+) -> HashMap<(usize, u64), f64> {
+    let table = get_rethrow_table(N);
+
+    let (mut probs, throws_left) = match dice {
+        Some((dice, throws_left)) => {
+            let mut probs = vec![0.0; table.dice.len()];
+            probs[get_dice_index::<N>(dice)] = 1.0;
+            (probs, throws_left)
+        }
+        None => (table.dice.iter().map(|d| d.probability()).collect(), 2),
+    };
+
+    for throws_left in (1..=throws_left).rev() {
+        let mut next = vec![0.0; probs.len()];
+
+        for (ind, &prob) in probs.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+            let rethrow = &solution.rethrows[throws_left][ind];
+            let &(_, keep_ind) = table.rethrows[ind]
+                .iter()
+                .find(|(sub_throw, _)| sub_throw == rethrow)
+                .unwrap();
+
+            for &(outcome, p, _) in &table.outcomes[keep_ind] {
+                next[outcome] += prob * p;
+            }
+        }
+
+        probs = next;
+    }
+
+    let mut outcomes = HashMap::new();
+
+    for (ind, &prob) in probs.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+        let cell_ind = solution.cells[ind];
+        let score = table.dice[ind].cell_score::<N>(cell_ind);
+
+        *outcomes.entry((cell_ind, score)).or_insert(0.0) += prob;
+    }
+
+    outcomes
+}
+
+type Layer = HashMap<(usize, u64), Partial>;
+
+fn add_turn<const N: u64>(
+    next: &mut Layer,
+    solver: &dyn Fn(usize, u64) -> TurnSolution,
+    mask: usize,
+    points_above: u64,
+    partial: &Partial,
+    dice: Option<(&DiceThrow, usize)>,
+) {
+    let solution = solver(mask, points_above);
+
+    for ((cell_ind, score), prob) in turn_outcomes::<N>(&solution, dice) {
+        let (gain, n_points_above) =
+            fill_cell::<N>(cell_ind, score, points_above);
+
+        next.entry((mask & !(1 << cell_ind), n_points_above))
+            .or_insert_with(Partial::new)
+            .add(partial, gain as usize, prob);
+    }
+}
+
+fn next_layer<const N: u64>(policy: &Policy, layer: Layer) -> Layer {
+    // Every thread takes a run of states in the order of the units of the
+    // lookup tables, so it only reads each unit once.
+    let mut layer: Vec<_> = layer.into_iter().collect();
+    layer.sort_unstable_by_key(|&((mask, points_above), _)| {
+        (points_above, mask)
+    });
+    let chunk_size = layer.len() / *NUM_CPUS + 1;
+    let layer = Arc::new(layer);
+
+    let handles: Vec<_> = (0..*NUM_CPUS)
+        .map(|t| {
+            let policy = policy.clone();
+            let layer = layer.clone();
+            spawn(move || {
+                let solver = turn_solver::<N>(&policy);
+                let mut next = HashMap::new();
+
+                for ((mask, points_above), partial) in
+                    layer.iter().skip(t * chunk_size).take(chunk_size)
+                {
+                    add_turn::<N>(
+                        &mut next,
+                        &*solver,
+                        *mask,
+                        *points_above,
+                        partial,
+                        None,
+                    );
+                }
+
+                next
+            })
+        })
+        .collect();
+
+    let mut next: Layer = HashMap::new();
+
+    for handle in handles {
+        for (key, partial) in handle.join().unwrap() {
+            next.entry(key)
+                .or_insert_with(Partial::new)
+                .add(&partial, 0, 1.0);
+        }
+    }
+
+    next
+}
+
+/// Whether the distributions are practical for `N` dice. With 6 dice a layer
+/// has up to about 15.7 million states of free cells and points above, each
+/// with a distribution over hundreds of totals, which takes far too much
+/// memory and time.
+pub fn is_practical<const N: u64>() -> bool {
+    N == 5
+}
+
+/// Distribution of the points still to get when playing the rest of the game
+/// by `policy`, from the free cells in `mask` and `points_above`. If `dice` is
+/// given the current turn continues from there. Only for 5 dice.
+pub fn remaining_distribution<const N: u64>(
+    policy: &Policy,
+    mask: usize,
+    points_above: u64,
+    dice: Option<(&DiceThrow, usize)>,
+) -> ScoreDistribution {
+    assert!(
+        is_practical::<N>(),
+        "Score distributions are only practical for 5 dice"
+    );
+
+    let start = Partial {
+        start: 0,
+        probs: vec![1.0],
+    };
+
+    let mut layer: Layer = HashMap::new();
+
+    if mask == 0 {
+        layer.insert((0, points_above), start);
+    } else {
+        let solver = turn_solver::<N>(policy);
+        add_turn::<N>(&mut layer, &*solver, mask, points_above, &start, dice);
+    }
+
+    for _ in 1..mask.count_ones() {
+        layer = next_layer::<N>(policy, layer);
+    }
+
+    let mut total = Partial::new();
+    for partial in layer.values() {
+        total.add(partial, 0, 1.0);
+    }
+
+    let mut probs = vec![0.0; total.start];
+    probs.append(&mut total.probs);
+
+    ScoreDistribution { probs }
+}
+
+/// Distribution of the final total when playing the rest of the game by
+/// `policy`. If `dice` is given the current turn continues from there.
+pub fn final_score_distribution<const N: u64>(
+    policy: &Policy,
+    points: &[Option<u64>],
+    dice: Option<(&DiceThrow, usize)>,
+) -> ScoreDistribution {
+    let cells: Vec<_> = points.iter().map(|x| x.is_none()).collect();
+    let points_above = points
+        .iter()
+        .take(6)
+        .filter_map(|x| x.as_ref())
+        .sum::<u64>()
+        .min(bonus_threshold::<N>());
+
+    remaining_distribution::<N>(
+        policy,
+        cells_to_mask(&cells),
+        points_above,
+        dice,
+    )
+    .shifted(get_total_score::<N>(points) as usize)
+}
+
+/// Checks that the probabilities of `dist` sum to 1 and that its mean is
+/// `expected`, up to `tolerance` for the rounding of the tables.
+pub fn check_distribution(
+    dist: &ScoreDistribution,
+    expected: f64,
+    tolerance: f64,
+) -> Result<(), String> {
+    println!("total probability  = {:.9}", dist.total());
+    println!("expected by table  = {:.3}", expected);
+
+    if (dist.total() - 1.0).abs() > 1e-6 {
+        return Err(format!(
+            "The probabilities sum to {:.9} instead of 1!",
+            dist.total()
+        ));
+    }
+    if (dist.mean() - expected).abs() > tolerance {
+        return Err(format!(
+            "The mean is {:.3} off from the expected score of the table!",
+            dist.mean() - expected
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn print_distribution<const N: u64>(
+    dist: &ScoreDistribution,
+    scores: &[usize],
+) {
+    let default_scores = match N {
+        5 => [200, 250, 300, 350],
+        6 => [300, 350, 400, 450],
+        _ => unreachable!(),
+    };
+
+    let scores = if scores.is_empty() {
+        &default_scores[..]
+    } else {
+        scores
+    };
+
+    println!("mean               = {:.3}", dist.mean());
+    println!("standard deviation = {:.3}", dist.variance().sqrt());
+
+    for &p in &[0.01, 0.05, 0.1, 0.25, 0.5, 0.75, 0.9, 0.95, 0.99] {
+        println!(
+            "percentile {:>2}      = {}",
+            (p * 100.0) as usize,
+            dist.percentile(p)
+        );
+    }
+
+    for &score in scores {
+        println!("P(score >= {:>3})   = {:.6}", score, dist.at_least(score));
+    }
+}
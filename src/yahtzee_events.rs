@@ -0,0 +1,122 @@
+use std::{
+    fs::OpenOptions,
+    io::{stderr, Write},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::OnceCell;
+
+static EVENTS: OnceCell<Mutex<Box<dyn Write + Send>>> = OnceCell::new();
+
+/// Writes the events of solver runs as JSON lines to `target`, either
+/// "stderr" or the path of a file to append to. Without it no events are
+/// written.
+pub fn set_events_target(target: &str) {
+    let out: Box<dyn Write + Send> = match target {
+        "stderr" => Box::new(stderr()),
+        path => Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .unwrap(),
+        ),
+    };
+
+    if EVENTS.set(Mutex::new(out)).is_err() {
+        panic!("The events target is already set!");
+    }
+}
+
+pub enum Event<'a> {
+    /// `done` of the `total` ticks of the layer were done by an earlier run.
+    LayerStarted {
+        layer: &'a str,
+        done: usize,
+        total: usize,
+    },
+    /// Sent once a second while a layer is computed. `speed` is ticks per
+    /// second and `eta` is in seconds, both averaged over the last minute.
+    Progress {
+        layer: &'a str,
+        done: usize,
+        total: usize,
+        speed: f32,
+        speed_per_thread: f32,
+        threads_running: usize,
+        threads_paused: usize,
+        eta: f32,
+    },
+    LayerFinished {
+        layer: &'a str,
+        seconds: f32,
+    },
+}
+
+// JSON has no infinity or NaN, which the speed and eta are before anything
+// is done.
+fn number(x: f32) -> String {
+    if x.is_finite() {
+        format!("{}", x)
+    } else {
+        "null".to_owned()
+    }
+}
+
+impl<'a> Event<'a> {
+    fn to_json(&self) -> String {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+
+        // Layers are named like 2_1, which needs no escaping.
+        match self {
+            Event::LayerStarted { layer, done, total } => format!(
+                "{{\"event\":\"layer_started\",\"time\":{:.3},\
+                \"layer\":\"{}\",\"done\":{},\"total\":{}}}",
+                time, layer, done, total
+            ),
+            Event::Progress {
+                layer,
+                done,
+                total,
+                speed,
+                speed_per_thread,
+                threads_running,
+                threads_paused,
+                eta,
+            } => format!(
+                "{{\"event\":\"progress\",\"time\":{:.3},\"layer\":\"{}\",\
+                \"done\":{},\"total\":{},\"speed\":{},\
+                \"speed_per_thread\":{},\"threads_running\":{},\
+                \"threads_paused\":{},\"paused\":{},\"eta\":{}}}",
+                time,
+                layer,
+                done,
+                total,
+                number(*speed),
+                number(*speed_per_thread),
+                threads_running,
+                threads_paused,
+                *threads_running == 0 && *threads_paused > 0,
+                number(*eta)
+            ),
+            Event::LayerFinished { layer, seconds } => format!(
+                "{{\"event\":\"layer_finished\",\"time\":{:.3},\
+                \"layer\":\"{}\",\"seconds\":{:.3}}}",
+                time, layer, seconds
+            ),
+        }
+    }
+}
+
+/// Writes `event` to the event stream, if there is one.
+pub fn emit(event: Event) {
+    if let Some(events) = EVENTS.get() {
+        let mut out = events.lock().unwrap();
+        writeln!(out, "{}", event.to_json()).unwrap();
+        out.flush().unwrap();
+    }
+}
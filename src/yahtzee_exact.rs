@@ -0,0 +1,247 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{Display, Error, Formatter},
+    iter::Sum,
+    ops::{Add, Mul},
+};
+
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_strats::{get_index_name, load_all_tables, new_throw, DiceIter},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: u128,
+    den: u128,
+}
+
+impl Rational {
+    pub fn new(num: u128, den: u128) -> Self {
+        let gcd = num.gcd(&den);
+
+        Self {
+            num: num / gcd,
+            den: den / gcd,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl From<u64> for Rational {
+    fn from(n: u64) -> Self {
+        Self::new(n as u128, 1)
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let den = self.den.lcm(&rhs.den);
+
+        Self::new(self.num * (den / self.den) + rhs.num * (den / rhs.den), den)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let a = self.num.gcd(&rhs.den);
+        let b = rhs.num.gcd(&self.den);
+
+        Self::new(
+            (self.num / a) * (rhs.num / b),
+            (self.den / b) * (rhs.den / a),
+        )
+    }
+}
+
+impl Zero for Rational {
+    fn zero() -> Self {
+        Self::new(0, 1)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+}
+
+impl One for Rational {
+    fn one() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+impl Sum for Rational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |a, x| a + x)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn exact_probability(dice: &DiceThrow) -> Rational {
+    let (num, den) = dice.probability_ratio();
+
+    Rational::new(num as u128, den as u128)
+}
+
+pub fn exact_expected_score(
+    orig_dice: &DiceThrow,
+    sub_throw: &DiceThrow,
+    scores: &HashMap<DiceThrow, Rational>,
+) -> Rational {
+    DiceIter::new(sub_throw.amt_dice())
+        .map(|new| {
+            *scores.get(&new_throw(orig_dice, sub_throw, &new)).unwrap()
+                * exact_probability(&new)
+        })
+        .sum()
+}
+
+fn make_exact_next_score_table(
+    prev_table: &HashMap<DiceThrow, Rational>,
+) -> HashMap<DiceThrow, Rational> {
+    prev_table
+        .keys()
+        .map(|throw| {
+            (
+                throw.clone(),
+                throw
+                    .clone()
+                    .into_sub_throw_iter()
+                    .map(|sub_throw| {
+                        exact_expected_score(throw, &sub_throw, prev_table)
+                    })
+                    .max()
+                    .unwrap(),
+            )
+        })
+        .collect()
+}
+
+pub fn make_exact_tables<const N: u64>(
+) -> Vec<Vec<HashMap<DiceThrow, Rational>>> {
+    let amt_cells = match N {
+        5 => 15,
+        6 => 20,
+        _ => unreachable!(),
+    };
+
+    let mut scores = vec![(0..amt_cells)
+        .map(|cell_ind| {
+            DiceIter::new(N)
+                .map(|throw| {
+                    let score = throw.cell_score::<N>(cell_ind);
+                    (throw, Rational::from(score))
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>()];
+
+    for _ in 0..2 {
+        scores.push(
+            scores
+                .last()
+                .unwrap()
+                .iter()
+                .map(make_exact_next_score_table)
+                .collect(),
+        );
+    }
+
+    scores
+}
+
+pub fn check_tables<const N: u64>() {
+    let exact = make_exact_tables::<N>();
+    let (scores, strats) = load_all_tables::<N>();
+
+    println!(
+        "{:<16}{:>6}  {:>40}{:>12}{:>12}{:>12}{:>8}",
+        "cell", "throws", "exact", "", "float", "max diff", "subopt"
+    );
+
+    for cell_ind in 0..exact[0].len() {
+        for throws_left in 0..exact.len() {
+            let exact_table = &exact[throws_left][cell_ind];
+            let float_table = &scores[throws_left][cell_ind];
+
+            let fresh_exact: Rational = DiceIter::new(N)
+                .map(|throw| {
+                    *exact_table.get(&throw).unwrap()
+                        * exact_probability(&throw)
+                })
+                .sum();
+
+            let fresh_float: f64 = DiceIter::new(N)
+                .map(|throw| {
+                    float_table.get(&throw).unwrap() * throw.probability()
+                })
+                .sum();
+
+            let max_diff = exact_table
+                .iter()
+                .map(|(throw, x)| {
+                    (x.to_f64() - float_table.get(throw).unwrap()).abs()
+                })
+                .fold(0.0, f64::max);
+
+            // Rethrows chosen by the float strats that are not exactly optimal.
+            let suboptimal = if throws_left > 0 {
+                exact_table
+                    .iter()
+                    .filter(|&(throw, x)| {
+                        let sub_throw =
+                            strats[throws_left][cell_ind].get(throw).unwrap();
+                        exact_expected_score(
+                            throw,
+                            sub_throw,
+                            &exact[throws_left - 1][cell_ind],
+                        ) != *x
+                    })
+                    .count()
+            } else {
+                0
+            };
+
+            println!(
+                "{:<16}{:>6}  {:>40}{:>12.6}{:>12.6}{:>12.2e}{:>8}",
+                get_index_name::<N>(cell_ind),
+                throws_left + 1,
+                format!("{}", fresh_exact),
+                fresh_exact.to_f64(),
+                fresh_float,
+                max_diff,
+                suboptimal
+            );
+        }
+    }
+}
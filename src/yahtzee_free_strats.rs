@@ -1,9 +1,15 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    fs::{create_dir_all, read_to_string, remove_file, File, OpenOptions},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    convert::TryInto,
+    fmt::{self, Display},
+    fs::{
+        create_dir_all, read, read_to_string, remove_file, write, File,
+        OpenOptions,
+    },
     io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
-    path::Path,
+    path::{Path, PathBuf},
     process::Command,
+    sync::{Arc, Mutex},
     thread::{sleep, spawn},
     time::{Duration, Instant},
 };
@@ -11,14 +17,34 @@ use std::{
 use crate::{
     bitfield_array_file::{self, BitfieldArrayFile},
     yahtzee::DiceThrow,
-    yahtzee_strats::{re_throw_iters, DiceIter},
+    yahtzee_control::{
+        held_threads, set_layer, set_progress, start_control_socket,
+        stop_requested,
+    },
+    yahtzee_distributed::{
+        become_worker, claim_unit, clear_locks, finish_part, finish_unit,
+        is_claimed, is_stale, is_verified, is_worker, mark_verified, part_path,
+        release_unit, WORKER_ID,
+    },
+    yahtzee_events::{emit, Event},
+    yahtzee_manifest::{
+        clear_manifest, file_entry, is_intact, is_recorded, read_manifest,
+        record_entries, record_files, FileEntry,
+    },
+    yahtzee_objective::Objective,
+    yahtzee_strats::{get_dice_index, get_rethrow_table, kept_dice, DiceIter},
+    yahtzee_turn::TurnSolution,
 };
 
+use crossbeam_channel::{Receiver, Sender};
+
+use num_traits::Float;
+
 use num_integer::binomial;
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 
-static NUM_CPUS: Lazy<usize> = Lazy::new(|| {
+pub(crate) static NUM_CPUS: Lazy<usize> = Lazy::new(|| {
     if Path::new("cpu_count.txt").exists() {
         read_to_string("cpu_count.txt").unwrap().parse().unwrap()
     } else {
@@ -26,7 +52,7 @@ static NUM_CPUS: Lazy<usize> = Lazy::new(|| {
     }
 });
 
-static LOOKUP_PATH: Lazy<String> =
+pub(crate) static LOOKUP_PATH: Lazy<String> =
     Lazy::new(|| read_to_string("lookup_path.txt").unwrap());
 
 static SCORES_PATH: Lazy<String> =
@@ -74,13 +100,126 @@ static CELLS: Lazy<Vec<(Vec<Vec<Vec<bool>>>, Vec<HashMap<Vec<bool>, usize>>)>> =
             .collect()
     });
 
-static DICE_INDEX: Lazy<Vec<HashMap<DiceThrow, usize>>> = Lazy::new(|| {
-    [5, 6]
+// For every set of filled upper cells, which points above can be reached
+// with them, capped at the bonus.
+static REACHABLE_POINTS_ABOVE: Lazy<Vec<Vec<Vec<bool>>>> = Lazy::new(|| {
+    [(5, 64), (6, 85)]
         .iter()
-        .map(|&n| DiceIter::new(n).enumerate().map(|(i, d)| (d, i)).collect())
+        .map(|&(n, amt)| {
+            (0..1 << 6)
+                .map(|filled: usize| {
+                    let mut reachable = vec![false; amt];
+                    reachable[0] = true;
+
+                    for i in (0..6).filter(|i| filled & 1 << i != 0) {
+                        let mut next = vec![false; amt];
+                        for points_above in (0..amt).filter(|&x| reachable[x]) {
+                            for k in 0..=n {
+                                next[(points_above + k * (i + 1))
+                                    .min(amt - 1)] = true;
+                            }
+                        }
+                        reachable = next;
+                    }
+
+                    reachable
+                })
+                .collect()
+        })
         .collect()
 });
 
+pub fn is_reachable<const N: u64>(cells: &[bool], points_above: usize) -> bool {
+    let filled: usize = (0..6).filter(|&i| !cells[i]).map(|i| 1 << i).sum();
+
+    REACHABLE_POINTS_ABOVE[n_to_ind::<N>()][filled][points_above]
+}
+
+// Only the cell sets that can have `points_above` are stored in the tables
+// for it, in the same order as in `CELLS`.
+fn stored_cell_inds<const N: u64>(
+    free_cells: usize,
+    points_above: usize,
+) -> Vec<usize> {
+    CELLS[n_to_ind::<N>()].0[free_cells]
+        .iter()
+        .enumerate()
+        .filter(|(_, cells)| is_reachable::<N>(cells, points_above))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Position of each cell set among the stored ones, or usize::MAX.
+fn stored_ranks<const N: u64>(
+    free_cells: usize,
+    points_above: usize,
+) -> Vec<usize> {
+    let mut ranks = vec![usize::MAX; amt_cell_ind::<N>(free_cells)];
+
+    for (rank, cell_ind) in stored_cell_inds::<N>(free_cells, points_above)
+        .into_iter()
+        .enumerate()
+    {
+        ranks[cell_ind] = rank;
+    }
+
+    ranks
+}
+
+fn amt_stored<const N: u64>(free_cells: usize, points_above: usize) -> usize {
+    CELLS[n_to_ind::<N>()].0[free_cells]
+        .iter()
+        .filter(|cells| is_reachable::<N>(cells, points_above))
+        .count()
+}
+
+// Index of `dice` with `cells` free in a table file for `points_above` of
+// `file_len` bytes with `bits` bits per entry. Tables from before the
+// unreachable cell sets were left out store every cell set, which shows in
+// the file size.
+fn stored_index<const N: u64>(
+    cells: &[bool],
+    dice: &DiceThrow,
+    points_above: usize,
+    file_len: u64,
+    bits: usize,
+) -> usize {
+    get_dice_index::<N>(dice)
+        + stored_rank::<N>(cells, points_above, file_len, bits)
+            * amt_dice_index::<N>()
+}
+
+// Position of `cells` among the cell sets stored in a strats file of
+// `file_len` bytes with `bits` per entry.
+fn stored_rank<const N: u64>(
+    cells: &[bool],
+    points_above: usize,
+    file_len: u64,
+    bits: usize,
+) -> usize {
+    let free_cells = cells.iter().filter(|&&b| b).count();
+    let &cell_ind = CELLS[n_to_ind::<N>()].1[free_cells].get(cells).unwrap();
+
+    let full_len =
+        (amt_cell_ind::<N>(free_cells) * amt_dice_index::<N>() * bits / 8)
+            as u64;
+
+    if file_len == full_len {
+        cell_ind
+    } else {
+        assert!(
+            is_reachable::<N>(cells, points_above),
+            "{} points above can not be reached with these cells filled",
+            points_above
+        );
+
+        CELLS[n_to_ind::<N>()].0[free_cells][..cell_ind]
+            .iter()
+            .filter(|cells| is_reachable::<N>(cells, points_above))
+            .count()
+    }
+}
+
 fn points_for_single_cell<const N: u64>(
     cell_ind: usize,
     dice: DiceThrow,
@@ -88,13 +227,17 @@ fn points_for_single_cell<const N: u64>(
 ) -> u64 {
     let score = dice.cell_score::<N>(cell_ind);
 
+    let bonus_threshold = match N {
+        5 => 63,
+        6 => 84,
+        _ => unreachable!(),
+    };
+
+    // The bonus is only given the turn the threshold is reached.
     let effective_score = if (cell_ind < 6)
-        && (points_above + score
-            >= match N {
-                5 => 63,
-                6 => 84,
-                _ => unreachable!(),
-            }) {
+        && points_above < bonus_threshold
+        && (points_above + score >= bonus_threshold)
+    {
         score
             + match N {
                 5 => 50,
@@ -108,7 +251,7 @@ fn points_for_single_cell<const N: u64>(
     effective_score
 }
 
-fn amt_dice_index<const N: u64>() -> usize {
+pub(crate) fn amt_dice_index<const N: u64>() -> usize {
     match N {
         5 => 252,
         6 => 462,
@@ -116,7 +259,7 @@ fn amt_dice_index<const N: u64>() -> usize {
     }
 }
 
-fn amt_cells<const N: u64>() -> usize {
+pub(crate) fn amt_cells<const N: u64>() -> usize {
     match N {
         5 => 15,
         6 => 20,
@@ -128,7 +271,7 @@ fn amt_cell_ind<const N: u64>(amt_free: usize) -> usize {
     binomial(amt_cells::<N>(), amt_free)
 }
 
-fn amt_points_above<const N: u64>() -> usize {
+pub(crate) fn amt_points_above<const N: u64>() -> usize {
     match N {
         5 => 64,
         6 => 85,
@@ -144,30 +287,243 @@ fn n_to_ind<const N: u64>() -> usize {
     }
 }
 
-fn make_init_scores<const N: u64, const BITS: usize>() {
-    let p = Path::new(&*LOOKUP_PATH);
+/// Directory of the tables for `objective`, relative to the lookup path. The
+/// expected score keeps the original place.
+pub fn tree_dir<const N: u64>(objective: Objective) -> String {
+    match objective {
+        Objective::Expected => format!("{}", N),
+        _ => format!("{}/objectives/{}", N, objective),
+    }
+}
+
+fn tree_path<const N: u64>(objective: Objective) -> PathBuf {
+    Path::new(&*LOOKUP_PATH).join(tree_dir::<N>(objective))
+}
+
+/// How the scores of a tree are stored. `F64` also does the sums in `f64`,
+/// while `U16` is fixed point with `U16_SCALE` steps per point and sums in
+/// `f32` like `F32`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Precision {
+    F32,
+    F64,
+    U16,
+}
+
+const U16_SCALE: f64 = 64.0;
+
+impl Precision {
+    pub fn parse(name: &str) -> Option<Precision> {
+        match name {
+            "f32" => Some(Precision::F32),
+            "f64" => Some(Precision::F64),
+            "u16" => Some(Precision::U16),
+            _ => None,
+        }
+    }
+
+    // Largest rounding of a stored value.
+    fn rounding(&self) -> f64 {
+        match self {
+            Precision::U16 => 0.5 / U16_SCALE,
+            _ => 0.0,
+        }
+    }
+
+    pub fn bytes(&self) -> usize {
+        match self {
+            Precision::F32 => 4,
+            Precision::F64 => 8,
+            Precision::U16 => 2,
+        }
+    }
+
+    fn write<T: Float, W: Write>(&self, w: &mut W, value: T) {
+        match self {
+            Precision::F32 => {
+                w.write_all(&value.to_f32().unwrap().to_le_bytes())
+            }
+            Precision::F64 => {
+                w.write_all(&value.to_f64().unwrap().to_le_bytes())
+            }
+            Precision::U16 => {
+                let fixed = (value.to_f64().unwrap() * U16_SCALE)
+                    .round()
+                    .clamp(0.0, u16::MAX as f64)
+                    as u16;
+                w.write_all(&fixed.to_le_bytes())
+            }
+        }
+        .unwrap();
+    }
+
+    fn decode<T: Float>(&self, bytes: &[u8]) -> T {
+        match self {
+            Precision::F32 => {
+                T::from(f32::from_le_bytes(bytes.try_into().unwrap())).unwrap()
+            }
+            Precision::F64 => {
+                T::from(f64::from_le_bytes(bytes.try_into().unwrap())).unwrap()
+            }
+            Precision::U16 => T::from(
+                u16::from_le_bytes(bytes.try_into().unwrap()) as f64
+                    / U16_SCALE,
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Precision::F32 => write!(f, "f32"),
+            Precision::F64 => write!(f, "f64"),
+            Precision::U16 => write!(f, "u16"),
+        }
+    }
+}
+
+// The metadata of a tree is stored as key=value lines in meta.txt.
+fn read_meta(tree: &Path) -> HashMap<String, String> {
+    match read_to_string(tree.join("meta.txt")) {
+        Ok(meta) => meta
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn write_meta<const N: u64>(objective: Objective, precision: Precision) {
+    let path = tree_path::<N>(objective);
+    create_dir_all(&path).unwrap();
+
+    write(
+        path.join("meta.txt"),
+        format!(
+            "objective={}\nprecision={}\nbonus=once\n",
+            objective, precision
+        ),
+    )
+    .unwrap();
+}
+
+// Trees without a precision in the metadata are stored as `f32`.
+fn tree_precision(tree: &Path) -> Precision {
+    read_meta(tree)
+        .get("precision")
+        .map_or(Precision::F32, |x| Precision::parse(x).unwrap())
+}
+
+// Trees from before the metadata have no meta.txt and hold the expected score.
+fn check_meta<const N: u64>(objective: Objective) {
+    let meta = read_meta(&tree_path::<N>(objective));
+
+    let stored = match meta.get("objective") {
+        Some(x) => Objective::parse(x),
+        None if objective == Objective::Expected => Some(objective),
+        None => {
+            panic!("No tables in {}, run init first!", tree_dir::<N>(objective))
+        }
+    };
+
+    assert_eq!(
+        stored,
+        Some(objective),
+        "The tables in {} are for another objective!",
+        tree_dir::<N>(objective)
+    );
+
+    // Older trees gave the bonus again for every cell filled after reaching
+    // it, which can not be mixed with layers that give it once.
+    assert!(
+        meta.get("bonus").map(|x| x.as_str()) == Some("once"),
+        "The tables in {} give the bonus more than once, run init again!",
+        tree_dir::<N>(objective)
+    );
+}
+
+// The layers of a tree in the order they are computed, as free cells and
+// throws left.
+fn layers<const N: u64>() -> Vec<(usize, usize)> {
+    (1..=amt_cells::<N>())
+        .flat_map(|free_cells| (0..3).map(move |t| (free_cells, t)))
+        .collect()
+}
+
+// The files a layer writes for `points_above`, relative to the tree.
+fn layer_files(layer: &str, points_above: usize) -> Vec<String> {
+    vec![
+        format!("scores/{}/{}.bin", layer, points_above),
+        format!("strats/{}/{}.bin", layer, points_above),
+    ]
+}
+
+// Moves the files of a finished unit in place and records them.
+fn finish_layer_files(tree: &Path, layer: &str, points_above: usize) {
+    let names = layer_files(layer, points_above);
+
+    for name in &names {
+        finish_part(&tree.join(name));
+    }
+
+    record_files(tree, &names);
+}
+
+// Removes the unfinished files of a unit that was given to another worker.
+fn discard_layer_files(tree: &Path, layer: &str, points_above: usize) {
+    for name in layer_files(layer, points_above) {
+        remove_file(part_path(&tree.join(name))).ok();
+    }
+}
+
+fn make_init_scores<const N: u64, const BITS: usize>(
+    objective: Objective,
+    precision: Precision,
+) {
+    write_meta::<N>(objective, precision);
+    clear_manifest(&tree_path::<N>(objective));
+    clear_locks(&tree_path::<N>(objective));
+
+    init_layer::<N, BITS>(objective, precision, HashSet::new());
+}
+
+// Makes the layer with a single free cell for every points above not in
+// `complete`.
+fn init_layer<const N: u64, const BITS: usize>(
+    objective: Objective,
+    precision: Precision,
+    complete: HashSet<usize>,
+) {
+    let p = tree_path::<N>(objective);
 
-    let strats_path = p.join(format!("{}/strats/1_0/", N));
+    let strats_path = p.join("strats/1_0/");
     create_dir_all(&strats_path).unwrap();
 
-    let scores_path = p.join(format!("{}/scores/1_0/", N));
+    let scores_path = p.join("scores/1_0/");
     create_dir_all(&scores_path).unwrap();
 
-    for points_above in 0..amt_points_above::<N>() {
+    for points_above in
+        (0..amt_points_above::<N>()).filter(|i| !complete.contains(i))
+    {
         let mut scores_file = BufWriter::new(
             OpenOptions::new()
                 .create(true)
                 .truncate(true)
                 .write(true)
-                .open(scores_path.join(format!("{}.bin", points_above)))
+                .open(part_path(
+                    &scores_path.join(format!("{}.bin", points_above)),
+                ))
                 .unwrap(),
         );
 
-        let mut strats_file = BitfieldArrayFile::<BITS>::open(
-            strats_path.join(format!("{}.bin", points_above)),
-        );
+        let mut strats_file = BitfieldArrayFile::<BITS>::open(part_path(
+            &strats_path.join(format!("{}.bin", points_above)),
+        ));
 
-        for cell_ind in 0..amt_cells::<N>() {
+        for cell_ind in stored_cell_inds::<N>(1, points_above) {
             for dice in DiceIter::new(N) {
                 let score = points_for_single_cell::<N>(
                     cell_ind,
@@ -177,54 +533,66 @@ fn make_init_scores<const N: u64, const BITS: usize>() {
 
                 strats_file.push(num_to_bits(cell_ind));
 
-                scores_file
-                    .write_all(&(score as f32).to_le_bytes())
-                    .unwrap();
+                precision.write(&mut scores_file, score as f64);
             }
         }
 
         strats_file.flush();
         scores_file.flush().unwrap();
+        drop(scores_file);
+        drop(strats_file);
+
+        finish_layer_files(&p, "1_0", points_above);
     }
 }
 
-fn load_scores<const N: u64>(
+fn load_scores<const N: u64, T: Float>(
+    objective: Objective,
+    precision: Precision,
     free_cells: usize,
     throws_left: usize,
     points_above: u64,
-) -> Vec<f32> {
-    let mut file = File::open(Path::new(&*LOOKUP_PATH).join(format!(
-        "{}/scores/{}_{}/{}.bin",
-        N, free_cells, throws_left, points_above
+) -> Vec<T> {
+    let mut file = File::open(tree_path::<N>(objective).join(format!(
+        "scores/{}_{}/{}.bin",
+        free_cells, throws_left, points_above
     )))
     .unwrap();
 
-    let chunk_size = 4 * amt_cell_ind::<N>(free_cells) * amt_dice_index::<N>();
+    let cell_size = precision.bytes() * amt_dice_index::<N>();
+
+    let stored_inds = stored_cell_inds::<N>(free_cells, points_above as usize);
 
-    let mut buf = vec![0; chunk_size];
+    // Tables from before the unreachable cell sets were left out store every
+    // cell set, which shows in the file size. Only the stored ones are kept,
+    // so layers computed from either layout are the same.
+    let full_len = amt_cell_ind::<N>(free_cells) * cell_size;
+
+    let buf = if file.metadata().unwrap().len() == full_len as u64 {
+        let mut full = vec![0; full_len];
+        file.read_exact(&mut full).unwrap();
+
+        stored_inds
+            .iter()
+            .flat_map(|&i| full[i * cell_size..(i + 1) * cell_size].to_vec())
+            .collect()
+    } else {
+        let mut buf = vec![0; stored_inds.len() * cell_size];
+        file.read_exact(&mut buf).unwrap();
 
-    file.read_exact(&mut buf).unwrap();
+        buf
+    };
 
     let scores = buf
-        .chunks(4)
-        .map(|bytes| {
-            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
-        })
+        .chunks(precision.bytes())
+        .map(|bytes| precision.decode(bytes))
         .collect();
 
     scores
 }
 
 fn get_index<const N: u64>(dice: &DiceThrow, cell_ind: usize) -> usize {
-    let dice_ind = DICE_INDEX[match N {
-        5 => 0,
-        6 => 1,
-        _ => unreachable!(),
-    }]
-    .get(dice)
-    .unwrap();
-
-    dice_ind + cell_ind * amt_dice_index::<N>()
+    get_dice_index::<N>(dice) + cell_ind * amt_dice_index::<N>()
 }
 
 fn rethrow_bits<const BITS: usize>(
@@ -245,29 +613,69 @@ fn rethrow_bits<const BITS: usize>(
     bits
 }
 
-fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
-    free_cells: usize,
-    throws_left: usize,
-) {
-    println!(
-        "Computing rethrows for {} free cells and {} throws left:",
-        free_cells, throws_left
-    );
-
-    let supertimer = Instant::now();
+struct WorkerProgress {
+    count: usize,
+    timer: Instant,
+    paused: bool,
+    progress_s: Sender<usize>,
+    pause_r: Receiver<()>,
+    wake_r: Receiver<()>,
+}
 
-    let scores_path = Path::new(&*LOOKUP_PATH)
-        .join(format!("{}/scores/{}_{}/", N, free_cells, throws_left));
+impl WorkerProgress {
+    // Counts one finished entry, reports progress once a second and sleeps
+    // while the worker is paused.
+    fn tick(&mut self) {
+        self.count += 1;
+
+        if self.timer.elapsed() >= Duration::from_secs(1) {
+            self.timer += Duration::from_secs(1);
+            self.progress_s.send(self.count).unwrap();
+            self.count = 0;
+            if let Ok(()) = self.pause_r.try_recv() {
+                self.paused = true;
+            }
+        }
 
-    let strats_path = Path::new(&*LOOKUP_PATH)
-        .join(format!("{}/strats/{}_{}/", N, free_cells, throws_left));
+        while self.paused {
+            sleep(Duration::from_secs(1));
+            if let Ok(()) = self.wake_r.try_recv() {
+                self.paused = false;
+            }
+        }
+    }
+}
 
-    create_dir_all(&scores_path).unwrap();
-    create_dir_all(&strats_path).unwrap();
+fn read_resume_file() -> HashSet<usize> {
+    if Path::new("resume.txt").exists() {
+        BufReader::new(File::open("resume.txt").unwrap())
+            .lines()
+            .map(|l| l.unwrap().parse::<usize>().unwrap())
+            .collect()
+    } else {
+        HashSet::new()
+    }
+}
 
-    let n = amt_cell_ind::<N>(free_cells)
-        * amt_points_above::<N>()
-        * amt_dice_index::<N>();
+// Runs `work` for every points above not in `complete` on `NUM_CPUS`
+// threads, with progress printing, pausing through hold_up and stopping
+// early through wrap_up_temp. `amounts[points_above]` is the amount of ticks
+// `work` does for that points above. The files of `layer` in `tree` are
+// moved in place and recorded in the manifest once `work` is done with them.
+// A distributed worker first claims every points above, and calls `skip` for
+// the ones another worker has.
+fn run_points_above_workers<const N: u64, F, G>(
+    tree: PathBuf,
+    layer: String,
+    amounts: Vec<usize>,
+    complete: HashSet<usize>,
+    work: F,
+    skip: G,
+) where
+    F: Fn(usize, &mut WorkerProgress) + Send + Sync + 'static,
+    G: Fn(usize) + Send + Sync + 'static,
+{
+    let n = amounts.iter().sum::<usize>();
 
     let (progress_s, progress_r) = crossbeam_channel::unbounded();
 
@@ -276,15 +684,6 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
 
     let (index_s, index_r) = crossbeam_channel::unbounded();
 
-    let complete = if Path::new("resume.txt").exists() {
-        BufReader::new(File::open("resume.txt").unwrap())
-            .lines()
-            .map(|l| l.unwrap().parse::<usize>().unwrap())
-            .collect()
-    } else {
-        HashSet::new()
-    };
-
     for points_above in
         (0..amt_points_above::<N>()).filter(|i| !complete.contains(i))
     {
@@ -295,10 +694,18 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
 
     let (points_done_s, points_done_r) = crossbeam_channel::unbounded();
 
+    let layer_timer = Instant::now();
+
+    emit(Event::LayerStarted {
+        layer: &layer,
+        done: complete.iter().map(|&x| amounts[x]).sum(),
+        total: n,
+    });
+
+    let progress_layer = layer.clone();
+
     let progress_handle = spawn(move || {
-        let mut i = amt_cell_ind::<N>(free_cells)
-            * amt_dice_index::<N>()
-            * complete.len();
+        let mut i = complete.iter().map(|&x| amounts[x]).sum::<usize>();
         let mut timer = Instant::now();
         let mut paused = 0;
         let mut to_be_paused;
@@ -308,14 +715,24 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
         let mut speed_buffer = VecDeque::new();
         let mut threads_buffer = VecDeque::new();
         let mut current_counter;
-        let mut done_file = OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open("resume.txt")
-            .unwrap();
+        // Workers share their working directory, so only a single process
+        // keeps resume.txt.
+        let mut done_file = if is_worker() {
+            None
+        } else {
+            Some(
+                OpenOptions::new()
+                    .create(true)
+                    .truncate(true)
+                    .write(true)
+                    .open("resume.txt")
+                    .unwrap(),
+            )
+        };
         for x in complete {
-            writeln!(done_file, "{}", x).unwrap();
+            if let Some(done_file) = &mut done_file {
+                writeln!(done_file, "{}", x).unwrap();
+            }
         }
         while amt_done < *NUM_CPUS {
             current_counter = progress_r.try_iter().sum::<usize>();
@@ -332,21 +749,7 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
                 threads_buffer.pop_front();
             }
 
-            if Path::new("hold_up").exists() {
-                if let Ok(amt) =
-                    read_to_string("hold_up").unwrap().parse::<usize>()
-                {
-                    if amt_done > amt {
-                        to_be_paused = 0;
-                    } else {
-                        to_be_paused = amt - amt_done;
-                    }
-                } else {
-                    to_be_paused = *NUM_CPUS;
-                }
-            } else {
-                to_be_paused = 0;
-            }
+            to_be_paused = held_threads().saturating_sub(amt_done);
 
             if paused < to_be_paused {
                 for _ in 0..(to_be_paused - paused) {
@@ -364,7 +767,9 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
             }
 
             for done_ind in points_done_r.try_iter() {
-                writeln!(done_file, "{}", done_ind).unwrap();
+                if let Some(done_file) = &mut done_file {
+                    writeln!(done_file, "{}", done_ind).unwrap();
+                }
             }
 
             if paused != *NUM_CPUS {
@@ -389,6 +794,17 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
 
                 let eta = (n - i) as f32 / speed;
 
+                emit(Event::Progress {
+                    layer: &progress_layer,
+                    done: i,
+                    total: n,
+                    speed,
+                    speed_per_thread,
+                    threads_running: *NUM_CPUS - paused - amt_done,
+                    threads_paused: paused,
+                    eta,
+                });
+
                 let eta = match eta {
                     eta if eta < 60.0 => format!("{:.2} s", eta),
                     eta if eta < 3600.0 => {
@@ -405,7 +821,7 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
                     ),
                 };
 
-                println!(
+                let line = format!(
                     "{} / {} = {:.2}%    speed = {:.1}; {:.1} x {:.1}    eta = {}",
                     i,
                     n,
@@ -415,6 +831,20 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
                     amt_threads,
                     eta,
                 );
+
+                println!("{}", line);
+                set_progress(line);
+            } else {
+                emit(Event::Progress {
+                    layer: &progress_layer,
+                    done: i,
+                    total: n,
+                    speed: 0.0,
+                    speed_per_thread: 0.0,
+                    threads_running: 0,
+                    threads_paused: paused,
+                    eta: f32::INFINITY,
+                });
             }
 
             if timer.elapsed() < printerval {
@@ -423,101 +853,54 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
             }
         }
 
-        done_file.flush().unwrap();
+        if let Some(mut done_file) = done_file {
+            done_file.flush().unwrap();
+        }
     });
 
+    let work = Arc::new(work);
+    let skip = Arc::new(skip);
+
     let handles: Vec<_> = (0..*NUM_CPUS)
         .map(|i| {
-            let scores_path = scores_path.clone();
-            let strats_path = strats_path.clone();
-            let progress_s = progress_s.clone();
-            let pause_r = pause_r.clone();
-            let wake_r = wake_r.clone();
-            let mut paused = false;
+            let work = work.clone();
+            let skip = skip.clone();
+            let mut progress = WorkerProgress {
+                count: 0,
+                timer: Instant::now(),
+                paused: false,
+                progress_s: progress_s.clone(),
+                pause_r: pause_r.clone(),
+                wake_r: wake_r.clone(),
+            };
             let index_r = index_r.clone();
             let done_s = done_s.clone();
             let points_done_s = points_done_s.clone();
+            let tree = tree.clone();
+            let layer = layer.clone();
             spawn(move || {
-                let mut count = 0;
-                let mut timer = Instant::now();
                 while let Ok(points_above) = index_r.try_recv() {
-                    let mut scores_file = BufWriter::with_capacity(
-                        1024 * 1024,
-                        OpenOptions::new()
-                            .create(true)
-                            .truncate(true)
-                            .write(true)
-                            .open(
-                                scores_path
-                                    .join(format!("{}.bin", points_above)),
-                            )
-                            .unwrap(),
-                    );
-                    let mut strats_file = BitfieldArrayFile::<BITS>::open(
-                        strats_path.join(format!("{}.bin", points_above)),
-                    );
-
-                    let scores = load_scores::<N>(
-                        free_cells,
-                        throws_left - 1,
-                        points_above as u64,
-                    );
-                    for cell_ind in 0..amt_cell_ind::<N>(free_cells) {
-                        for dice in DiceIter::new(N) {
-                            let (sub_throw, score) = dice
-                                .clone()
-                                .into_sub_throw_iter()
-                                .map(|sub_throw| {
-                                    (
-                                        sub_throw.clone(),
-                                        re_throw_iters(&dice, &sub_throw)
-                                            .map(|(throw, prob)| {
-                                                prob as f32
-                                                    * scores[get_index::<N>(
-                                                        &throw, cell_ind,
-                                                    )]
-                                            })
-                                            .sum::<f32>(),
-                                    )
-                                })
-                                .max_by(|(_, a), (_, b)| {
-                                    a.partial_cmp(b).unwrap()
-                                })
-                                .unwrap();
-                            scores_file
-                                .write_all(&score.to_le_bytes())
-                                .unwrap();
-                            strats_file.push(rethrow_bits(&dice, &sub_throw));
-
-                            count += 1;
+                    if is_worker() && !claim_unit(&tree, &layer, points_above) {
+                        skip(points_above);
+                        continue;
+                    }
 
-                            if timer.elapsed() >= Duration::from_secs(1) {
-                                timer += Duration::from_secs(1);
-                                progress_s.send(count).unwrap();
-                                count = 0;
-                                if let Ok(()) = pause_r.try_recv() {
-                                    paused = true;
-                                }
-                            }
+                    work(points_above, &mut progress);
 
-                            while paused {
-                                sleep(Duration::from_secs(1));
-                                if let Ok(()) = wake_r.try_recv() {
-                                    paused = false;
-                                }
-                            }
-                        }
+                    if is_worker() && !finish_unit(&tree, &layer, points_above)
+                    {
+                        discard_layer_files(&tree, &layer, points_above);
+                    } else {
+                        finish_layer_files(&tree, &layer, points_above);
+                        points_done_s.send(points_above).unwrap();
                     }
-                    scores_file.flush().unwrap();
-                    strats_file.flush();
-                    points_done_s.send(points_above).unwrap();
 
                     if Path::new("wrap_up_temp").exists() {
                         break;
                     }
                 }
 
-                progress_s.send(count).unwrap();
+                progress.progress_s.send(progress.count).unwrap();
                 done_s.send(i).unwrap();
             })
         })
@@ -529,215 +912,441 @@ fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
 
     progress_handle.join().unwrap();
 
-    let complete = if Path::new("resume.txt").exists() {
-        BufReader::new(File::open("resume.txt").unwrap())
-            .lines()
-            .map(|l| l.unwrap().parse::<usize>().unwrap())
-            .collect()
-    } else {
-        HashSet::new()
-    };
+    emit(Event::LayerFinished {
+        layer: &layer,
+        seconds: layer_timer.elapsed().as_secs_f32(),
+    });
 
-    if (0..amt_points_above::<N>()).all(|i| complete.contains(&i)) {
+    let complete = read_resume_file();
+
+    if !is_worker()
+        && (0..amt_points_above::<N>()).all(|i| complete.contains(&i))
+    {
         match std::fs::remove_file(Path::new("resume.txt")) {
             _ => (),
         }
     }
-
-    println!("took {:?}\n", supertimer.elapsed());
 }
 
-fn num_to_bits<const BITS: usize>(n: usize) -> [bool; BITS] {
-    let mut buf = [false; BITS];
-
-    for i in 0..BITS {
-        buf[i] = ((n >> i) & 1) != 0;
+fn make_rethrows_and_scores<const N: u64, const BITS: usize>(
+    objective: Objective,
+    free_cells: usize,
+    throws_left: usize,
+    complete: HashSet<usize>,
+) {
+    match tree_precision(&tree_path::<N>(objective)) {
+        Precision::F64 => rethrow_layer::<N, BITS, f64>(
+            objective,
+            Precision::F64,
+            free_cells,
+            throws_left,
+            complete,
+        ),
+        precision => rethrow_layer::<N, BITS, f32>(
+            objective,
+            precision,
+            free_cells,
+            throws_left,
+            complete,
+        ),
     }
-
-    buf
 }
 
-fn make_cell_choice_and_scores<const N: u64, const BITS: usize>(
+fn rethrow_layer<const N: u64, const BITS: usize, T>(
+    objective: Objective,
+    precision: Precision,
     free_cells: usize,
-) {
-    println!("Computing cell choice for {} free cells:", free_cells);
+    throws_left: usize,
+    complete: HashSet<usize>,
+) where
+    T: Float + Send + Sync + 'static,
+{
+    let layer = format!(
+        "rethrows for {} free cells and {} throws left",
+        free_cells, throws_left
+    );
+    println!("Computing {}:", layer);
+    set_layer(layer);
 
     let supertimer = Instant::now();
 
-    let scores_path = Path::new(&*LOOKUP_PATH)
-        .join(format!("{}/scores/{}_{}/", N, free_cells, 0));
+    let scores_path = tree_path::<N>(objective)
+        .join(format!("scores/{}_{}/", free_cells, throws_left));
 
-    let strats_path = Path::new(&*LOOKUP_PATH)
-        .join(format!("{}/strats/{}_{}/", N, free_cells, 0));
+    let strats_path = tree_path::<N>(objective)
+        .join(format!("strats/{}_{}/", free_cells, throws_left));
 
     create_dir_all(&scores_path).unwrap();
     create_dir_all(&strats_path).unwrap();
 
-    let mut scores_buf = Vec::new();
-
-    let mut highets_points_in_buffer = match N {
-        5 => 30,
-        6 => 36,
-        _ => unreachable!(),
-    };
+    run_points_above_workers::<N, _, _>(
+        tree_path::<N>(objective),
+        format!("{}_{}", free_cells, throws_left),
+        (0..amt_points_above::<N>())
+            .map(|x| amt_stored::<N>(free_cells, x) * amt_dice_index::<N>())
+            .collect(),
+        complete,
+        move |points_above, progress| {
+            let mut scores_file = BufWriter::with_capacity(
+                1024 * 1024,
+                OpenOptions::new()
+                    .create(true)
+                    .truncate(true)
+                    .write(true)
+                    .open(part_path(
+                        &scores_path.join(format!("{}.bin", points_above)),
+                    ))
+                    .unwrap(),
+            );
+            let mut strats_file = BitfieldArrayFile::<BITS>::open(part_path(
+                &strats_path.join(format!("{}.bin", points_above)),
+            ));
 
-    for points_above in 0..=match N {
-        5 => 30,
-        6 => 36,
-        _ => unimplemented!(),
-    } {
-        scores_buf.push(load_scores::<N>(free_cells - 1, 2, points_above));
+            let scores = load_scores::<N, T>(
+                objective,
+                precision,
+                free_cells,
+                throws_left - 1,
+                points_above as u64,
+            );
+            let rethrow_table = get_rethrow_table(N);
+            // The layer below stores the same cell sets in the same order.
+            for stored_ind in 0..amt_stored::<N>(free_cells, points_above) {
+                // The score of a rethrow only depends on the dice that are
+                // kept, which many dice states share.
+                let offset = stored_ind * amt_dice_index::<N>();
+                let keep_scores = objective.keep_values(
+                    rethrow_table,
+                    &scores[offset..offset + amt_dice_index::<N>()],
+                );
+                for (dice, rethrows) in
+                    DiceIter::new(N).zip(rethrow_table.rethrows.iter())
+                {
+                    let (sub_throw, score) = rethrows
+                        .iter()
+                        .map(|(sub_throw, keep_ind)| {
+                            (sub_throw, keep_scores[*keep_ind])
+                        })
+                        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                        .unwrap();
+                    precision.write(&mut scores_file, score);
+                    strats_file.push(rethrow_bits(&dice, sub_throw));
+
+                    progress.tick();
+                }
+            }
+            scores_file.flush().unwrap();
+            strats_file.flush();
+        },
+        |_| (),
+    );
+
+    println!("took {:?}\n", supertimer.elapsed());
+}
+
+fn num_to_bits<const BITS: usize>(n: usize) -> [bool; BITS] {
+    let mut buf = [false; BITS];
+
+    for i in 0..BITS {
+        buf[i] = ((n >> i) & 1) != 0;
     }
 
-    let mut i: u64 = 0;
-    let n = amt_cell_ind::<N>(free_cells)
-        * amt_points_above::<N>()
-        * amt_dice_index::<N>();
+    buf
+}
+
+fn max_points_for_upper_cell<const N: u64>() -> usize {
+    match N {
+        5 => 30,
+        6 => 36,
+        _ => unreachable!(),
+    }
+}
 
-    let mut timer = Instant::now();
+type WindowScores<T> = (Vec<T>, Vec<usize>);
 
-    for points_above in 0..amt_points_above::<N>() {
-        if Path::new("hold_up").exists() {
-            if let Err(_) = read_to_string("hold_up").unwrap().parse::<usize>()
-            {
-                sleep(Duration::from_secs(10));
-                timer = Instant::now();
-            }
-        }
+type WindowSlot<T> = Arc<OnceCell<Arc<WindowScores<T>>>>;
 
-        let mut scores_file = BufWriter::with_capacity(
-            1024 * 1024,
-            OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(scores_path.join(format!("{}.bin", points_above)))
-                .unwrap(),
-        );
-        let mut strats_file = BitfieldArrayFile::<BITS>::open(
-            strats_path.join(format!("{}.bin", points_above)),
-        );
+// The scores of the layer below, with the stored position of each cell set,
+// for the points above that running workers may still need. They are loaded
+// when first asked for and dropped once every points above that could need
+// them is done.
+struct ScoreWindow<const N: u64, T> {
+    objective: Objective,
+    precision: Precision,
+    free_cells: usize,
+    loaded: Mutex<HashMap<usize, WindowSlot<T>>>,
+    unfinished: Mutex<BTreeSet<usize>>,
+}
 
-        let mut free_inds = Vec::new();
-        for cell_ind in 0..amt_cell_ind::<N>(free_cells) {
-            for dice in DiceIter::new(N) {
-                let mut cells =
-                    CELLS[n_to_ind::<N>()].0[free_cells][cell_ind].clone();
+impl<const N: u64, T: Float> ScoreWindow<N, T> {
+    fn get(&self, points_above: usize) -> Arc<WindowScores<T>> {
+        // Only the slot is taken under the lock, so while one points above
+        // is read workers can get the others. Those that want the same one
+        // wait for it.
+        let slot = self
+            .loaded
+            .lock()
+            .unwrap()
+            .entry(points_above)
+            .or_default()
+            .clone();
+
+        slot.get_or_init(|| {
+            Arc::new((
+                load_scores::<N, T>(
+                    self.objective,
+                    self.precision,
+                    self.free_cells,
+                    2,
+                    points_above as u64,
+                ),
+                stored_ranks::<N>(self.free_cells, points_above),
+            ))
+        })
+        .clone()
+    }
 
-                free_inds.clear();
-                free_inds.extend((0..amt_cells::<N>()).filter(|&i| cells[i]));
+    fn finish(&self, points_above: usize) {
+        let mut unfinished = self.unfinished.lock().unwrap();
+        unfinished.remove(&points_above);
 
-                let (best_ind, score) = free_inds
-                    .iter()
-                    .map(|&i| {
-                        cells[i] = false;
+        let lowest = unfinished.iter().next().copied().unwrap_or(usize::MAX);
+        self.loaded.lock().unwrap().retain(|&k, _| k >= lowest);
+    }
+}
+
+fn make_cell_choice_and_scores<const N: u64, const BITS: usize>(
+    objective: Objective,
+    free_cells: usize,
+    complete: HashSet<usize>,
+) {
+    match tree_precision(&tree_path::<N>(objective)) {
+        Precision::F64 => cell_choice_layer::<N, BITS, f64>(
+            objective,
+            Precision::F64,
+            free_cells,
+            complete,
+        ),
+        precision => cell_choice_layer::<N, BITS, f32>(
+            objective, precision, free_cells, complete,
+        ),
+    }
+}
+
+fn cell_choice_layer<const N: u64, const BITS: usize, T>(
+    objective: Objective,
+    precision: Precision,
+    free_cells: usize,
+    complete: HashSet<usize>,
+) where
+    T: Float + Send + Sync + 'static,
+{
+    let layer = format!("cell choice for {} free cells", free_cells);
+    println!("Computing {}:", layer);
+    set_layer(layer);
+
+    let supertimer = Instant::now();
+
+    let scores_path =
+        tree_path::<N>(objective).join(format!("scores/{}_{}/", free_cells, 0));
+
+    let strats_path =
+        tree_path::<N>(objective).join(format!("strats/{}_{}/", free_cells, 0));
 
-                        let &n_cell_ind = CELLS[n_to_ind::<N>()].1
-                            [free_cells - 1]
-                            .get(&cells)
-                            .unwrap();
+    create_dir_all(&scores_path).unwrap();
+    create_dir_all(&strats_path).unwrap();
+
+    let window = Arc::new(ScoreWindow::<N, T> {
+        objective,
+        precision,
+        free_cells: free_cells - 1,
+        loaded: Mutex::new(HashMap::new()),
+        unfinished: Mutex::new(
+            (0..amt_points_above::<N>())
+                .filter(|i| !complete.contains(i))
+                .collect(),
+        ),
+    });
+    let skipped_window = window.clone();
+
+    run_points_above_workers::<N, _, _>(
+        tree_path::<N>(objective),
+        format!("{}_{}", free_cells, 0),
+        (0..amt_points_above::<N>())
+            .map(|x| amt_stored::<N>(free_cells, x) * amt_dice_index::<N>())
+            .collect(),
+        complete,
+        move |points_above, progress| {
+            // scores_buf[k] holds the scores for points_above + k, up to the
+            // last one which is used for reaching the bonus.
+            let scores_buf: Vec<_> = (points_above
+                ..amt_points_above::<N>()
+                    .min(points_above + max_points_for_upper_cell::<N>() + 1))
+                .map(|k| window.get(k))
+                .collect();
+
+            let mut scores_file = BufWriter::with_capacity(
+                1024 * 1024,
+                OpenOptions::new()
+                    .create(true)
+                    .truncate(true)
+                    .write(true)
+                    .open(part_path(
+                        &scores_path.join(format!("{}.bin", points_above)),
+                    ))
+                    .unwrap(),
+            );
+            let mut strats_file = BitfieldArrayFile::<BITS>::open(part_path(
+                &strats_path.join(format!("{}.bin", points_above)),
+            ));
 
-                        let n_ind = get_index::<N>(&dice, n_cell_ind);
+            let mut free_inds = Vec::new();
+            for cell_ind in stored_cell_inds::<N>(free_cells, points_above) {
+                for dice in DiceIter::new(N) {
+                    let mut cells =
+                        CELLS[n_to_ind::<N>()].0[free_cells][cell_ind].clone();
 
-                        let additional_points = dice.cell_score::<N>(i);
+                    free_inds.clear();
+                    free_inds
+                        .extend((0..amt_cells::<N>()).filter(|&i| cells[i]));
 
-                        let mut points_offset =
-                            if i < 6 { additional_points as usize } else { 0 };
+                    let (best_ind, score) = free_inds
+                        .iter()
+                        .map(|&i| {
+                            cells[i] = false;
 
-                        let mut bonus = 0.0;
+                            let &n_cell_ind = CELLS[n_to_ind::<N>()].1
+                                [free_cells - 1]
+                                .get(&cells)
+                                .unwrap();
 
-                        if points_offset + points_above
-                            >= amt_points_above::<N>() - 1
-                        {
-                            points_offset = scores_buf.len() - 1;
-                            bonus = match N {
-                                5 => 50.0,
-                                6 => 100.0,
-                                _ => unreachable!(),
+                            let additional_points = dice.cell_score::<N>(i);
+
+                            let mut points_offset = if i < 6 {
+                                additional_points as usize
+                            } else {
+                                0
+                            };
+
+                            let mut bonus = T::zero();
+
+                            // Points above are capped at the threshold, so
+                            // only crossing it gives the bonus.
+                            if points_offset + points_above
+                                >= amt_points_above::<N>() - 1
+                            {
+                                points_offset = scores_buf.len() - 1;
+                                if points_above < amt_points_above::<N>() - 1 {
+                                    bonus = T::from(match N {
+                                        5 => 50.0,
+                                        6 => 100.0,
+                                        _ => unreachable!(),
+                                    })
+                                    .unwrap()
+                                }
                             }
-                        }
 
-                        let score = scores_buf[points_offset][n_ind]
-                            + additional_points as f32
-                            + bonus;
+                            let (scores, ranks) = &*scores_buf[points_offset];
 
-                        cells[i] = true;
+                            let n_ind =
+                                get_index::<N>(&dice, ranks[n_cell_ind]);
 
-                        (i, score)
-                    })
-                    .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                    .unwrap();
+                            let score = scores[n_ind]
+                                + T::from(additional_points).unwrap()
+                                + bonus;
 
-                scores_file.write_all(&score.to_le_bytes()).unwrap();
-                strats_file.push(num_to_bits::<BITS>(best_ind));
+                            cells[i] = true;
 
-                i += 1;
+                            (i, score)
+                        })
+                        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                        .unwrap();
 
-                if timer.elapsed() >= Duration::from_secs(1) {
-                    timer += Duration::from_secs(1);
+                    precision.write(&mut scores_file, score);
+                    strats_file.push(num_to_bits::<BITS>(best_ind));
 
-                    println!(
-                        "{} / {} = {:.2}%",
-                        i,
-                        n,
-                        (i as f32) / (n as f32) * 100.0
-                    );
+                    progress.tick();
                 }
             }
-        }
 
-        scores_buf.remove(0);
-        if highets_points_in_buffer + 1 < amt_points_above::<N>() {
-            highets_points_in_buffer += 1;
-            scores_buf.push(load_scores::<N>(
-                free_cells - 1,
-                2,
-                highets_points_in_buffer as u64,
-            ));
-        }
+            scores_file.flush().unwrap();
+            strats_file.flush();
 
-        scores_file.flush().unwrap();
-        strats_file.flush();
-    }
+            drop(scores_buf);
+            window.finish(points_above);
+        },
+        move |points_above| skipped_window.finish(points_above),
+    );
 
     println!("took {:?}\n", supertimer.elapsed());
 }
 
-pub fn init(n: &str) {
+pub fn init(n: &str, objective: Objective, precision: Precision) {
     match n {
-        "5" => make_init_scores::<5, 4>(),
-        "6" => make_init_scores::<6, 5>(),
+        "5" => make_init_scores::<5, 4>(objective, precision),
+        "6" => make_init_scores::<6, 5>(objective, precision),
         _ => println!("invalid number of dice"),
     }
 }
 
-pub fn resume_calcs_5(mut free_cells: usize, throws_left: usize) {
+pub fn resume_calcs_5(
+    mut free_cells: usize,
+    throws_left: usize,
+    objective: Objective,
+) {
+    check_meta::<5>(objective);
+    start_control_socket();
+
     let timer = Instant::now();
     if throws_left == 1 {
-        make_rethrows_and_scores::<5, 5>(free_cells, 1);
+        make_rethrows_and_scores::<5, 5>(
+            objective,
+            free_cells,
+            1,
+            read_resume_file(),
+        );
     }
-    if Path::new("wrap_up").exists() {
+    if stop_requested() {
         return;
     }
     if throws_left > 0 {
-        make_rethrows_and_scores::<5, 5>(free_cells, 2);
+        make_rethrows_and_scores::<5, 5>(
+            objective,
+            free_cells,
+            2,
+            read_resume_file(),
+        );
         free_cells += 1;
     }
-    if Path::new("wrap_up").exists() {
+    if stop_requested() {
         return;
     }
 
     for free_cells in free_cells..=15 {
-        make_cell_choice_and_scores::<5, 4>(free_cells);
-        if Path::new("wrap_up").exists() {
+        make_cell_choice_and_scores::<5, 4>(
+            objective,
+            free_cells,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
-        make_rethrows_and_scores::<5, 5>(free_cells, 1);
-        if Path::new("wrap_up").exists() {
+        make_rethrows_and_scores::<5, 5>(
+            objective,
+            free_cells,
+            1,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
-        make_rethrows_and_scores::<5, 5>(free_cells, 2);
-        if Path::new("wrap_up").exists() {
+        make_rethrows_and_scores::<5, 5>(
+            objective,
+            free_cells,
+            2,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
     }
@@ -745,37 +1354,386 @@ pub fn resume_calcs_5(mut free_cells: usize, throws_left: usize) {
     println!("Total time: {:?}", timer.elapsed());
 }
 
-pub fn resume_calcs_6(mut free_cells: usize, throws_left: usize) {
+pub fn resume_calcs_6(
+    mut free_cells: usize,
+    throws_left: usize,
+    objective: Objective,
+) {
+    check_meta::<6>(objective);
+    start_control_socket();
+
     if throws_left == 1 {
-        make_rethrows_and_scores::<6, 6>(free_cells, 1);
+        make_rethrows_and_scores::<6, 6>(
+            objective,
+            free_cells,
+            1,
+            read_resume_file(),
+        );
     }
-    if Path::new("wrap_up").exists() {
+    if stop_requested() {
         return;
     }
     if throws_left > 0 {
-        make_rethrows_and_scores::<6, 6>(free_cells, 2);
+        make_rethrows_and_scores::<6, 6>(
+            objective,
+            free_cells,
+            2,
+            read_resume_file(),
+        );
         free_cells += 1;
     }
-    if Path::new("wrap_up").exists() {
+    if stop_requested() {
         return;
     }
 
     for free_cells in free_cells..=20 {
-        make_cell_choice_and_scores::<6, 5>(free_cells);
-        if Path::new("wrap_up").exists() {
+        make_cell_choice_and_scores::<6, 5>(
+            objective,
+            free_cells,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
-        make_rethrows_and_scores::<6, 6>(free_cells, 1);
-        if Path::new("wrap_up").exists() {
+        make_rethrows_and_scores::<6, 6>(
+            objective,
+            free_cells,
+            1,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
-        make_rethrows_and_scores::<6, 6>(free_cells, 2);
-        if Path::new("wrap_up").exists() {
+        make_rethrows_and_scores::<6, 6>(
+            objective,
+            free_cells,
+            2,
+            read_resume_file(),
+        );
+        if stop_requested() {
             break;
         }
     }
 }
 
+/// Computes the layers of the tree for `objective` that are not finished in
+/// its manifest, in order. Within a layer, only the points above whose files
+/// are missing, or have another size than recorded, are recomputed.
+pub fn continue_calcs(n: &str, objective: Objective) {
+    match n {
+        "5" => continue_tree::<5, 4, 5>(objective),
+        "6" => continue_tree::<6, 5, 6>(objective),
+        _ => println!("invalid number of dice"),
+    }
+}
+
+fn continue_tree<
+    const N: u64,
+    const CELL_BITS: usize,
+    const RETHROW_BITS: usize,
+>(
+    objective: Objective,
+) {
+    check_meta::<N>(objective);
+    start_control_socket();
+
+    let tree = tree_path::<N>(objective);
+    let manifest = read_manifest(&tree);
+
+    let layers: Vec<_> = layers::<N>()
+        .into_iter()
+        .map(|(f, t)| (f, t, format!("{}_{}", f, t)))
+        .collect();
+
+    // Whether the files of a unit are finished, going by the size or also
+    // by the checksum.
+    let is_done = |layer: &str, points_above, checksum: bool| {
+        layer_files(layer, points_above).iter().all(|name| {
+            if checksum {
+                is_intact(&tree, &manifest, name)
+            } else {
+                is_recorded(&tree, &manifest, name)
+            }
+        })
+    };
+
+    // Checking the checksums means reading the whole tree, so the layers
+    // before the last finished one only have their sizes checked. The last
+    // finished one is what the rest is computed from.
+    let first_open = layers
+        .iter()
+        .position(|(_, _, layer)| {
+            !(0..amt_points_above::<N>())
+                .all(|points_above| is_done(layer, points_above, false))
+        })
+        .unwrap_or(layers.len());
+
+    let timer = Instant::now();
+
+    for (k, (free_cells, throws_left, layer)) in layers.iter().enumerate() {
+        let (free_cells, throws_left) = (*free_cells, *throws_left);
+
+        let complete: HashSet<_> = (0..amt_points_above::<N>())
+            .filter(|&points_above| {
+                is_done(layer, points_above, k + 1 >= first_open)
+            })
+            .collect();
+
+        if complete.len() == amt_points_above::<N>() {
+            continue;
+        }
+
+        match (free_cells, throws_left) {
+            (1, 0) => init_layer::<N, CELL_BITS>(
+                objective,
+                tree_precision(&tree),
+                complete,
+            ),
+            (_, 0) => make_cell_choice_and_scores::<N, CELL_BITS>(
+                objective, free_cells, complete,
+            ),
+            _ => make_rethrows_and_scores::<N, RETHROW_BITS>(
+                objective,
+                free_cells,
+                throws_left,
+                complete,
+            ),
+        }
+
+        if stop_requested() {
+            return;
+        }
+    }
+
+    println!("Total time: {:?}", timer.elapsed());
+}
+
+// How long distributed workers and the coordinator wait before looking at
+// the tree again.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Works on the tree for `objective` together with other worker processes,
+/// possibly on other hosts sharing the lookup directory. Every unit of a
+/// layer is claimed through a lock file first, and the next layer is only
+/// started once the coordinator has verified the last one.
+pub fn work(n: &str, objective: Objective) {
+    match n {
+        "5" => work_on_tree::<5, 4, 5>(objective),
+        "6" => work_on_tree::<6, 5, 6>(objective),
+        _ => println!("invalid number of dice"),
+    }
+}
+
+fn work_on_tree<
+    const N: u64,
+    const CELL_BITS: usize,
+    const RETHROW_BITS: usize,
+>(
+    objective: Objective,
+) {
+    check_meta::<N>(objective);
+    become_worker();
+
+    println!("Working as {}", *WORKER_ID);
+
+    let tree = tree_path::<N>(objective);
+
+    for (free_cells, throws_left) in layers::<N>() {
+        let layer = format!("{}_{}", free_cells, throws_left);
+
+        // Units rejected by the coordinator are given back while waiting for
+        // it, so they are looked for until the layer is verified.
+        while !is_verified(&tree, &layer) {
+            let manifest = read_manifest(&tree);
+
+            let taken: HashSet<_> = (0..amt_points_above::<N>())
+                .filter(|&points_above| {
+                    is_claimed(&tree, &layer, points_above)
+                        || layer_files(&layer, points_above)
+                            .iter()
+                            .all(|name| is_recorded(&tree, &manifest, name))
+                })
+                .collect();
+
+            // The coordinator makes the first layer itself.
+            if taken.len() == amt_points_above::<N>()
+                || free_cells == 1 && throws_left == 0
+            {
+                if stop_requested() {
+                    return;
+                }
+                sleep(POLL_INTERVAL);
+                continue;
+            }
+
+            if throws_left == 0 {
+                make_cell_choice_and_scores::<N, CELL_BITS>(
+                    objective, free_cells, taken,
+                );
+            } else {
+                make_rethrows_and_scores::<N, RETHROW_BITS>(
+                    objective,
+                    free_cells,
+                    throws_left,
+                    taken,
+                );
+            }
+
+            if stop_requested() {
+                return;
+            }
+        }
+    }
+}
+
+/// Verifies the layers of the tree for `objective` that distributed workers
+/// compute, in order. A layer is verified once every unit has its files with
+/// the size and checksum of the manifest, and the scores have the size they
+/// should. Units that fail are removed and given back to the workers.
+pub fn coordinate(n: &str, objective: Objective) {
+    match n {
+        "5" => coordinate_tree::<5, 4>(objective),
+        "6" => coordinate_tree::<6, 5>(objective),
+        _ => println!("invalid number of dice"),
+    }
+}
+
+fn coordinate_tree<const N: u64, const CELL_BITS: usize>(objective: Objective) {
+    check_meta::<N>(objective);
+
+    let tree = tree_path::<N>(objective);
+    let precision = tree_precision(&tree);
+
+    let timer = Instant::now();
+
+    for (free_cells, throws_left) in layers::<N>() {
+        let layer = format!("{}_{}", free_cells, throws_left);
+
+        while !is_verified(&tree, &layer) {
+            let manifest = read_manifest(&tree);
+
+            let open: Vec<_> = (0..amt_points_above::<N>())
+                .filter(|&points_above| {
+                    !layer_files(&layer, points_above)
+                        .iter()
+                        .all(|name| is_recorded(&tree, &manifest, name))
+                })
+                .collect();
+            let amt_done = amt_points_above::<N>() - open.len();
+
+            if amt_done < amt_points_above::<N>() {
+                if free_cells == 1 && throws_left == 0 {
+                    init_layer::<N, CELL_BITS>(
+                        objective,
+                        precision,
+                        (0..amt_points_above::<N>())
+                            .filter(|i| !open.contains(i))
+                            .collect(),
+                    );
+                    continue;
+                }
+
+                for &points_above in &open {
+                    if is_stale(&tree, &layer, points_above) {
+                        println!(
+                            "Gave back {} for layer {}, its worker stopped",
+                            points_above, layer
+                        );
+                        release_unit(&tree, &layer, points_above);
+                    }
+                }
+
+                println!(
+                    "layer {}: {} / {} done, {} claimed",
+                    layer,
+                    amt_done,
+                    amt_points_above::<N>(),
+                    (0..amt_points_above::<N>())
+                        .filter(|&x| is_claimed(&tree, &layer, x))
+                        .count()
+                );
+                if stop_requested() {
+                    return;
+                }
+                sleep(POLL_INTERVAL);
+                continue;
+            }
+
+            // The checksums are computed again here, and the ones that
+            // match what the worker recorded go in the coordinator's own
+            // manifest.
+            let mut rejected = Vec::new();
+
+            for points_above in 0..amt_points_above::<N>() {
+                match verified_entries::<N>(
+                    &tree,
+                    &manifest,
+                    precision,
+                    free_cells,
+                    &layer,
+                    points_above,
+                ) {
+                    Some(entries) => record_entries(&tree, &entries),
+                    None => rejected.push(points_above),
+                }
+            }
+
+            for &points_above in &rejected {
+                println!("Rejected {} for layer {}", points_above, layer);
+
+                for name in layer_files(&layer, points_above) {
+                    remove_file(tree.join(name)).unwrap();
+                }
+                release_unit(&tree, &layer, points_above);
+            }
+
+            if rejected.is_empty() {
+                mark_verified(&tree, &layer);
+                println!("Verified layer {}", layer);
+            }
+        }
+
+        if stop_requested() {
+            return;
+        }
+    }
+
+    println!("Total time: {:?}", timer.elapsed());
+}
+
+// The entries of the files of a unit as computed here, if they match the
+// manifest and the scores have the size they should.
+fn verified_entries<const N: u64>(
+    tree: &Path,
+    manifest: &HashMap<String, FileEntry>,
+    precision: Precision,
+    free_cells: usize,
+    layer: &str,
+    points_above: usize,
+) -> Option<Vec<(String, FileEntry)>> {
+    let scores_size = precision.bytes()
+        * amt_stored::<N>(free_cells, points_above)
+        * amt_dice_index::<N>();
+
+    let entries: Vec<_> = layer_files(layer, points_above)
+        .into_iter()
+        .map(|name| {
+            let entry = file_entry(&tree.join(&name));
+            (name, entry)
+        })
+        .collect();
+
+    let matches = entries
+        .iter()
+        .all(|(name, entry)| manifest.get(name) == Some(entry));
+
+    if matches && entries[0].1.size == scores_size as u64 {
+        Some(entries)
+    } else {
+        None
+    }
+}
+
 fn get_dice_from_bits(orig_dice: &DiceThrow, bits: &[bool]) -> DiceThrow {
     let mut dice = DiceThrow::from([0; 6]);
 
@@ -789,28 +1747,31 @@ fn get_dice_from_bits(orig_dice: &DiceThrow, bits: &[bool]) -> DiceThrow {
 }
 
 fn cache_compressed_strats<const N: u64>(
+    objective: Objective,
     free_cells: usize,
     throws_left: usize,
     points_above: usize,
 ) {
     Command::new("7z")
         .arg("x")
-        .arg(Path::new(&*LOOKUP_PATH).join(format!("{}/strats.7z", N)))
+        .arg(tree_path::<N>(objective).join("strats.7z"))
         .arg(format!(
             "{}_{}/{}.bin",
             free_cells, throws_left, points_above
         ))
-        .arg(format!("-olookup/tmp/{}/strats/", N))
+        .arg(format!("-olookup/tmp/{}/strats/", tree_dir::<N>(objective)))
         .output()
         .unwrap();
 }
 
-pub fn get_rethrow_strat<const N: u64>(
-    cells: &[bool],
-    dice: &DiceThrow,
+// Where the strats of a unit of a tree are read from. With a compressed folder
+// they are unpacked to lookup/tmp when first needed.
+fn lookup_strats_path<const N: u64>(
+    objective: Objective,
+    free_cells: usize,
     throws_left: usize,
-    points_above: u64,
-) -> DiceThrow {
+    points_above: usize,
+) -> PathBuf {
     let local = Path::new("compressed").exists();
 
     let lookup_path = if local {
@@ -819,24 +1780,59 @@ pub fn get_rethrow_strat<const N: u64>(
         Path::new(&*LOOKUP_PATH)
     };
 
-    let points_above = if points_above as usize + 1 >= amt_points_above::<N>() {
-        amt_points_above::<N>() - 1
-    } else {
-        points_above as usize
-    };
-
-    let free_cells = cells.iter().filter(|&&b| b).count();
-    let &cell_ind = CELLS[n_to_ind::<N>()].1[free_cells].get(cells).unwrap();
-    let ind = get_index::<N>(dice, cell_ind);
     let path = lookup_path.join(format!(
         "{}/strats/{}_{}/{}.bin",
-        N, free_cells, throws_left, points_above,
+        tree_dir::<N>(objective),
+        free_cells,
+        throws_left,
+        points_above,
     ));
 
     if !path.exists() {
-        cache_compressed_strats::<N>(free_cells, throws_left, points_above);
+        cache_compressed_strats::<N>(
+            objective,
+            free_cells,
+            throws_left,
+            points_above,
+        );
     }
 
+    path
+}
+
+fn capped_points_above<const N: u64>(points_above: u64) -> usize {
+    if points_above as usize + 1 >= amt_points_above::<N>() {
+        amt_points_above::<N>() - 1
+    } else {
+        points_above as usize
+    }
+}
+
+pub fn get_rethrow_strat<const N: u64>(
+    objective: Objective,
+    cells: &[bool],
+    dice: &DiceThrow,
+    throws_left: usize,
+    points_above: u64,
+) -> DiceThrow {
+    let points_above = capped_points_above::<N>(points_above);
+
+    let free_cells = cells.iter().filter(|&&b| b).count();
+    let path = lookup_strats_path::<N>(
+        objective,
+        free_cells,
+        throws_left,
+        points_above,
+    );
+
+    let ind = stored_index::<N>(
+        cells,
+        dice,
+        points_above,
+        path.metadata().unwrap().len(),
+        N as usize,
+    );
+
     let rethrow = match N {
         5 => get_dice_from_bits(
             dice,
@@ -867,35 +1863,23 @@ fn get_ind_from_bits(bits: &[bool]) -> usize {
 }
 
 pub fn get_cell_strat<const N: u64>(
+    objective: Objective,
     cells: &[bool],
     dice: &DiceThrow,
     points_above: u64,
 ) -> usize {
-    let local = Path::new("compressed").exists();
-
-    let lookup_path = if local {
-        Path::new("lookup/tmp/")
-    } else {
-        Path::new(&*LOOKUP_PATH)
-    };
-
-    let points_above = if points_above as usize + 1 >= amt_points_above::<N>() {
-        amt_points_above::<N>() - 1
-    } else {
-        points_above as usize
-    };
+    let points_above = capped_points_above::<N>(points_above);
 
     let free_cells = cells.iter().filter(|&&b| b).count();
-    let &cell_ind = CELLS[n_to_ind::<N>()].1[free_cells].get(cells).unwrap();
-    let ind = get_index::<N>(dice, cell_ind);
-    let path = lookup_path.join(format!(
-        "{}/strats/{}_0/{}.bin",
-        N, free_cells, points_above
-    ));
-
-    if !path.exists() {
-        cache_compressed_strats::<N>(free_cells, 0, points_above);
-    }
+    let path = lookup_strats_path::<N>(objective, free_cells, 0, points_above);
+
+    let ind = stored_index::<N>(
+        cells,
+        dice,
+        points_above,
+        path.metadata().unwrap().len(),
+        N as usize - 1,
+    );
 
     match N {
         5 => get_ind_from_bits(&bitfield_array_file::get_bits::<_, 4>(
@@ -908,13 +1892,157 @@ pub fn get_cell_strat<const N: u64>(
     }
 }
 
+/// How a tree plays the turns with `free_cells` free and `points_above`
+/// reached, with the strats of the unit read whole instead of an entry at a
+/// time.
+pub struct LookupTurns<const N: u64> {
+    pub free_cells: usize,
+    pub points_above: usize,
+    // The strats files by throws left, so the cell choices first.
+    strats: Vec<Vec<u8>>,
+}
+
+impl<const N: u64> LookupTurns<N> {
+    pub fn load(
+        objective: Objective,
+        free_cells: usize,
+        points_above: usize,
+    ) -> Self {
+        Self {
+            free_cells,
+            points_above,
+            strats: (0..3)
+                .map(|throws_left| {
+                    read(lookup_strats_path::<N>(
+                        objective,
+                        free_cells,
+                        throws_left,
+                        points_above,
+                    ))
+                    .unwrap()
+                })
+                .collect(),
+        }
+    }
+
+    /// The play of the turn with `cells` free.
+    pub fn turn_solution(&self, cells: &[bool]) -> TurnSolution {
+        let rethrows = (0..3)
+            .map(|throws_left| {
+                if throws_left == 0 {
+                    return Vec::new();
+                }
+
+                let bytes = &self.strats[throws_left];
+                let offset = stored_rank::<N>(
+                    cells,
+                    self.points_above,
+                    bytes.len() as u64,
+                    N as usize,
+                ) * amt_dice_index::<N>();
+
+                DiceIter::new(N)
+                    .enumerate()
+                    .map(|(i, dice)| match N {
+                        5 => get_dice_from_bits(
+                            &dice,
+                            &bitfield_array_file::bits_at::<5>(
+                                bytes,
+                                offset + i,
+                            ),
+                        ),
+                        6 => get_dice_from_bits(
+                            &dice,
+                            &bitfield_array_file::bits_at::<6>(
+                                bytes,
+                                offset + i,
+                            ),
+                        ),
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let bytes = &self.strats[0];
+        let offset = stored_rank::<N>(
+            cells,
+            self.points_above,
+            bytes.len() as u64,
+            N as usize - 1,
+        ) * amt_dice_index::<N>();
+
+        let cells = (0..amt_dice_index::<N>())
+            .map(|i| match N {
+                5 => get_ind_from_bits(&bitfield_array_file::bits_at::<4>(
+                    bytes,
+                    offset + i,
+                )),
+                6 => get_ind_from_bits(&bitfield_array_file::bits_at::<5>(
+                    bytes,
+                    offset + i,
+                )),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        TurnSolution {
+            values: Vec::new(),
+            rethrows,
+            cells,
+        }
+    }
+}
+
+/// Expected remaining score by the tables of `objective` before the first
+/// throw of a turn with `cells` free and `points_above` reached, which is the
+/// expected score for `Objective::Expected`.
+pub fn lookup_turn_start_value<const N: u64>(
+    objective: Objective,
+    cells: &[bool],
+    points_above: u64,
+) -> f64 {
+    let points_above = capped_points_above::<N>(points_above);
+    let precision = tree_precision(&tree_path::<N>(objective));
+
+    let free_cells = cells.iter().filter(|&&b| b).count();
+    let scores = load_scores::<N, f64>(
+        objective,
+        precision,
+        free_cells,
+        2,
+        points_above as u64,
+    );
+
+    let &cell_ind = CELLS[n_to_ind::<N>()].1[free_cells].get(cells).unwrap();
+    let rank = stored_ranks::<N>(free_cells, points_above)[cell_ind];
+
+    DiceIter::new(N)
+        .map(|dice| dice.probability() * scores[get_index::<N>(&dice, rank)])
+        .sum()
+}
+
+/// How far the values of a tree can be off from the rounding of the stored
+/// values, at most half a step for every layer.
+pub fn lookup_rounding<const N: u64>(objective: Objective) -> f64 {
+    tree_precision(&tree_path::<N>(objective)).rounding()
+        * layers::<N>().len() as f64
+}
+
+/// Whether the scores that `get_score` reads are there.
+pub fn has_scores<const N: u64>() -> bool {
+    Path::new(&*SCORES_PATH)
+        .join(format!("{}/scores.7z", N))
+        .exists()
+}
+
 pub fn get_score<const N: u64>(
     cells: &[bool],
     dice: &DiceThrow,
     points_above: u64,
     throws_left: usize,
 ) -> f32 {
-    let free_cells = cells.iter().map(|&x| x).count();
+    let free_cells = cells.iter().filter(|&&x| x).count();
     let ans = {
         Command::new("7z")
             .arg("x")
@@ -927,21 +2055,29 @@ pub fn get_score<const N: u64>(
             .output()
             .unwrap();
 
-        let &cell_ind =
-            CELLS[n_to_ind::<N>()].1[free_cells].get(cells).unwrap();
-        let ind = get_index::<N>(dice, cell_ind);
-
         let mut f = File::open(format!(
             "./tmp/{}/scores/{}_{}/{}.bin",
             N, free_cells, throws_left, points_above
         ))
         .unwrap();
 
-        f.seek(SeekFrom::Start(ind as u64 * 4)).unwrap();
-        let mut bytes = [0; 4];
-        f.read(&mut bytes).unwrap();
+        let precision =
+            tree_precision(&Path::new(&*SCORES_PATH).join(format!("{}", N)));
+
+        let ind = stored_index::<N>(
+            cells,
+            dice,
+            points_above as usize,
+            f.metadata().unwrap().len(),
+            precision.bytes() * 8,
+        );
+
+        f.seek(SeekFrom::Start((ind * precision.bytes()) as u64))
+            .unwrap();
+        let mut bytes = vec![0; precision.bytes()];
+        f.read_exact(&mut bytes).unwrap();
 
-        f32::from_le_bytes(bytes)
+        precision.decode(&bytes)
     };
 
     remove_file(format!(
@@ -953,4 +2089,65 @@ pub fn get_score<const N: u64>(
     ans
 }
 
+pub fn opening_book<const N: u64>(out_path: &str) {
+    let cells = vec![true; amt_cells::<N>()];
+
+    let scores: Vec<f64> = load_scores::<N, _>(
+        Objective::Expected,
+        tree_precision(&tree_path::<N>(Objective::Expected)),
+        amt_cells::<N>(),
+        2,
+        0,
+    );
+
+    let mut rows: Vec<_> = DiceIter::new(N)
+        .map(|dice| {
+            let rethrow = get_rethrow_strat::<N>(
+                Objective::Expected,
+                &cells,
+                &dice,
+                2,
+                0,
+            );
+            let score = scores[get_index::<N>(&dice, 0)];
+
+            (kept_dice(&dice, &rethrow), rethrow, dice, score)
+        })
+        .collect();
+
+    rows.sort_by(|(_, _, _, a), (_, _, _, b)| b.partial_cmp(a).unwrap());
+
+    let mut csv =
+        String::from("dice,keep,rethrow,probability,expected_score\n");
+
+    println!(
+        "{:<8}{:<8}{:<8}{:>12}{:>16}",
+        "dice", "keep", "rethrow", "probability", "expected score"
+    );
+
+    for (keep, rethrow, dice, score) in &rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            dice.digits(),
+            keep.digits(),
+            rethrow.digits(),
+            dice.probability(),
+            score
+        ));
+
+        println!(
+            "{:<8}{:<8}{:<8}{:>12.6}{:>16.3}",
+            dice.digits(),
+            keep.digits(),
+            rethrow.digits(),
+            dice.probability(),
+            score
+        );
+    }
+
+    write(out_path, csv).unwrap();
+
+    println!("Wrote {}", out_path);
+}
+
 pub fn test(_commands: &[&str]) {}
@@ -1,15 +1,25 @@
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     io::{stdin, stdout, Write},
     iter::Sum,
+    sync::Arc,
     vec,
 };
 
 use num_traits::Num;
 
 use crate::{
-    yahtzee_free_strats::{get_cell_strat, get_rethrow_strat, get_score},
+    yahtzee_distribution::{
+        final_score_distribution, is_practical, print_distribution, Policy,
+    },
+    yahtzee_free_strats::{get_score, has_scores},
+    yahtzee_objective::Objective,
+    yahtzee_player::Strategy,
     yahtzee_strats::{get_index_name, new_throw},
+    yahtzee_target::TargetStrategy,
+    yahtzee_turn_start::load_turn_start_values,
+    yahtzee_versus::{win_chances, DistributionCache, Goal, VersusStrategy},
     DiceThrow, HELP_CELL_NAMES,
 };
 
@@ -22,9 +32,38 @@ set points <cell> <points>: set a cell to a value. Get cell names by
     help cell names
 clear points <cell>: clears points
 advise <dice-left> <dice>: gives advice on what to do with the dice
+set target <score>: advise and auto play to maximize the chance of a total of
+    at least <score> instead of the expected score. Needs compute-target.
+    The opponents are kept, and changing them plays against them again.
+clear target: goes back to the best expected score
+set opponent <name> <cell> <points>: set a cell on the sheet of an opponent.
+    Advice and auto play then play against all opponents instead of for the
+    target, assuming they play for the best expected score. Needs
+    compute-turn-start and compute-target, and only works with 5 dice.
+clear opponent <name> <cell>: clears a cell on the sheet of an opponent
+remove opponent <name>: stops playing against an opponent
+display opponent <name>: display the points of an opponent
+set goal <win/rank>: play against the opponents for the chance of winning
+    (default) or for the best expected rank
+win-chance: chance of winning for you and every opponent from the current
+    throw, when everyone plays for the best expected score, and for you with
+    the advice against the opponents. Only with 5 dice.
 throw dice <N>: prints a dice throw of <N> dice
+compare <cell> <throws-left> <dice> <keep-a> <keep-b>: shows the distribution
+    of the final score in <cell> for two different keeps. Use - to keep
+    nothing.
+distribution [score...]: exact distribution of the final total from the
+    current throw when playing by the lookup tables of the expected score,
+    with the chance of reaching each [score]. Needs the lookup tables, and
+    only works with 5 dice.
 "#;
 
+const NOT_PRACTICAL_MSG: &str =
+    "Score distributions are only practical for 5 dice!";
+
+const NEEDS_SCORES_MSG: &str =
+    "The expected score needs the scores of the lookup tables!";
+
 fn tostr<T: Num + Display + PartialEq + Clone>(
     points: &Vec<Option<T>>,
     ind: &mut usize,
@@ -113,7 +152,7 @@ pub fn display_points<
     );
 }
 
-fn get_total_score<const N: u64>(points: &[Option<u64>]) -> u64 {
+pub fn get_total_score<const N: u64>(points: &[Option<u64>]) -> u64 {
     let points_above: u64 =
         points.iter().take(6).filter_map(|x| x.as_ref()).sum();
 
@@ -138,7 +177,51 @@ fn get_total_score<const N: u64>(points: &[Option<u64>]) -> u64 {
     total
 }
 
-pub fn start<const N: u64>() {
+// The distributions of the final totals of the opponents are only computed
+// when advice is needed, since they take a while early in their games.
+fn load_versus<const N: u64>(
+    versus: &mut Option<VersusStrategy>,
+    opponents: &BTreeMap<String, Vec<Option<u64>>>,
+    goal: Goal,
+    distributions: &mut Option<DistributionCache>,
+    turn_start_values: &mut Option<Arc<Vec<f32>>>,
+) {
+    if versus.is_some() || opponents.is_empty() {
+        return;
+    }
+
+    let distributions = distributions.get_or_insert_with(|| {
+        DistributionCache::new(
+            turn_start_values
+                .get_or_insert_with(|| Arc::new(load_turn_start_values::<N>()))
+                .clone(),
+        )
+    });
+
+    let dists: Vec<_> = opponents
+        .values()
+        .map(|sheet| distributions.get::<N>(sheet))
+        .collect();
+
+    *versus = Some(VersusStrategy::new::<N>(&dists, goal));
+}
+
+// Whichever of the target and the opponents was set last is played for.
+fn active_strategy<'a, const N: u64>(
+    strategy: &'a dyn Strategy<N>,
+    target: &'a Option<TargetStrategy>,
+    versus: &'a Option<VersusStrategy>,
+    target_last: bool,
+) -> &'a dyn Strategy<N> {
+    match (target, versus) {
+        (Some(target), _) if target_last => target,
+        (_, Some(versus)) => versus,
+        (Some(target), _) => target,
+        _ => strategy,
+    }
+}
+
+pub fn start<const N: u64>(strategy: &dyn Strategy<N>) {
     println!(
         "Welcome to the interactive guide of a free game with {} dice",
         N
@@ -156,6 +239,16 @@ pub fn start<const N: u64>() {
     let mut last_dice = DiceThrow::throw(N as usize);
     let mut throws_left = 2;
 
+    let mut turn_start_values = None;
+
+    let mut target: Option<TargetStrategy> = None;
+    let mut target_last = false;
+
+    let mut opponents: BTreeMap<String, Vec<Option<u64>>> = BTreeMap::new();
+    let mut goal = Goal::Win;
+    let mut distributions = None;
+    let mut versus: Option<VersusStrategy> = None;
+
     println!("Starting throw:\n{}", last_dice);
 
     loop {
@@ -193,20 +286,156 @@ pub fn start<const N: u64>() {
 
                 last_dice = throw;
             }
-            ["auto"] => {
-                let free_cells: Vec<_> =
-                    points.iter().map(|x| x.is_none()).collect();
+            ["set", "target", score] => {
+                if let Ok(score) = score.parse() {
+                    let t = TargetStrategy::new(score);
+                    let chance = t.with_solution::<N, _, _>(&points, |s| {
+                        s.value::<N>(&last_dice, throws_left)
+                    });
 
-                let points_above =
-                    points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+                    println!(
+                        "Playing to reach {}, with a chance of {:.4} from the \
+                        current throw.",
+                        score, chance
+                    );
 
-                if throws_left == 0 {
-                    let ind = get_cell_strat::<N>(
-                        &free_cells,
-                        &last_dice,
-                        points_above,
+                    if !opponents.is_empty() {
+                        println!(
+                            "Keeping the opponents, changing them plays \
+                            against them again."
+                        );
+                    }
+
+                    target = Some(t);
+                    target_last = true;
+                }
+            }
+            ["clear", "target"] => {
+                target = None;
+                target_last = false;
+
+                if opponents.is_empty() {
+                    println!("Playing for the best expected score.");
+                } else {
+                    println!("Playing against the opponents.");
+                }
+            }
+            ["set", "opponent", _, _, _] if !is_practical::<N>() => {
+                println!("{}", NOT_PRACTICAL_MSG)
+            }
+            ["set", "opponent", name, cell, pts] => {
+                let index = super::get_yahtzee_index::<N>(cell);
+                let pts = pts.parse().unwrap();
+
+                let sheet = opponents
+                    .entry(name.to_string())
+                    .or_insert_with(|| vec![None; points.len()]);
+                sheet[index] = Some(pts);
+                display_points::<_, N>(sheet, None, None);
+
+                target_last = false;
+                versus = None;
+            }
+            ["clear", "opponent", name, cell] => {
+                let index = super::get_yahtzee_index::<N>(cell);
+
+                if let Some(sheet) = opponents.get_mut(*name) {
+                    sheet[index] = None;
+                    target_last = false;
+                    versus = None;
+                }
+            }
+            ["remove", "opponent", name] => {
+                opponents.remove(*name);
+                target_last = false;
+                versus = None;
+
+                if opponents.is_empty() && target.is_some() {
+                    println!("Playing for the target.");
+                } else if opponents.is_empty() {
+                    println!("Playing for the best expected score.");
+                }
+            }
+            ["display", "opponent", name] => {
+                if let Some(sheet) = opponents.get(*name) {
+                    display_points::<_, N>(sheet, None, None);
+                }
+            }
+            ["set", "goal", name] => {
+                if let Some(g) = Goal::parse(name) {
+                    goal = g;
+                    target_last = false;
+                    versus = None;
+                }
+            }
+            ["win-chance"] if !is_practical::<N>() => {
+                println!("{}", NOT_PRACTICAL_MSG)
+            }
+            ["win-chance"] => {
+                load_versus::<N>(
+                    &mut versus,
+                    &opponents,
+                    goal,
+                    &mut distributions,
+                    &mut turn_start_values,
+                );
+
+                if let (Some(versus), Some(distributions)) =
+                    (&versus, &mut distributions)
+                {
+                    let mut dists = vec![final_score_distribution::<N>(
+                        &Policy::BestExpected(
+                            turn_start_values.clone().unwrap(),
+                        ),
+                        &points,
+                        Some((&last_dice, throws_left)),
+                    )];
+                    dists.extend(
+                        opponents
+                            .values()
+                            .map(|sheet| distributions.get::<N>(sheet)),
                     );
 
+                    let chances = win_chances(&dists);
+
+                    println!("you: {:.4}", chances[0]);
+                    for (name, chance) in opponents.keys().zip(&chances[1..]) {
+                        println!("{}: {:.4}", name, chance);
+                    }
+
+                    let value = versus.with_solution::<N, _, _>(&points, |s| {
+                        s.value::<N>(&last_dice, throws_left)
+                    });
+
+                    match versus.goal {
+                        Goal::Win => {
+                            println!("you with the advice: {:.4}", value)
+                        }
+                        Goal::Rank => println!(
+                            "expected rank with the advice: {:.3}",
+                            1.0 - value
+                        ),
+                    }
+                } else {
+                    println!("No opponents set!");
+                }
+            }
+            ["auto"] => {
+                if !target_last {
+                    load_versus::<N>(
+                        &mut versus,
+                        &opponents,
+                        goal,
+                        &mut distributions,
+                        &mut turn_start_values,
+                    );
+                }
+                let strategy =
+                    active_strategy(strategy, &target, &versus, target_last);
+
+                if throws_left == 0 {
+                    let ind = strategy.cell(&points, &last_dice);
+
                     let score = last_dice.cell_score::<N>(ind);
 
                     println!(
@@ -223,12 +452,8 @@ pub fn start<const N: u64>() {
 
                     println!("New throw:\n{}", last_dice);
                 } else {
-                    let rethrow = get_rethrow_strat::<N>(
-                        &free_cells,
-                        &last_dice,
-                        throws_left,
-                        points_above,
-                    );
+                    let rethrow =
+                        strategy.rethrow(&points, &last_dice, throws_left);
 
                     println!("Rethrowing:\n{}", rethrow);
 
@@ -253,19 +478,21 @@ pub fn start<const N: u64>() {
 
                 println!("You entered:\n{}\n", throw);
 
-                let free_cells: Vec<_> =
-                    points.iter().map(|x| x.is_none()).collect();
-
-                let points_above =
-                    points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+                if !target_last {
+                    load_versus::<N>(
+                        &mut versus,
+                        &opponents,
+                        goal,
+                        &mut distributions,
+                        &mut turn_start_values,
+                    );
+                }
+                let strategy =
+                    active_strategy(strategy, &target, &versus, target_last);
 
                 match throws_left {
                     0 => {
-                        let ind = get_cell_strat::<N>(
-                            &free_cells,
-                            &throw,
-                            points_above,
-                        );
+                        let ind = strategy.cell(&points, &throw);
 
                         let score = throw.cell_score::<N>(ind);
 
@@ -276,18 +503,45 @@ pub fn start<const N: u64>() {
                         );
                     }
                     1 | 2 => {
-                        let rethrow = get_rethrow_strat::<N>(
-                            &free_cells,
-                            &throw,
-                            throws_left,
-                            points_above,
-                        );
+                        let rethrow =
+                            strategy.rethrow(&points, &throw, throws_left);
 
                         println!("Rethrow:\n{}", rethrow);
                     }
                     _ => unreachable!(),
                 }
             }
+            ["compare", cell, throws_left, dice, keep_a, keep_b] => {
+                if let Ok(throws_left) = throws_left.parse() {
+                    super::compare_keeps::<N>(
+                        cell,
+                        throws_left,
+                        dice,
+                        keep_a,
+                        keep_b,
+                    );
+                }
+            }
+            ["distribution", ..] if !is_practical::<N>() => {
+                println!("{}", NOT_PRACTICAL_MSG)
+            }
+            ["distribution", scores @ ..] => {
+                let scores: Vec<_> =
+                    scores.iter().filter_map(|x| x.parse().ok()).collect();
+
+                let dist = final_score_distribution::<N>(
+                    &Policy::Lookup(Objective::Expected),
+                    &points,
+                    Some((&last_dice, throws_left)),
+                );
+
+                print_distribution::<N>(&dist, &scores);
+            }
+            ["expected-remaining"] | ["expected-total"]
+                if !has_scores::<N>() =>
+            {
+                println!("{}", NEEDS_SCORES_MSG)
+            }
             ["expected-remaining"] => {
                 let free_cells: Vec<_> =
                     points.iter().map(|x| x.is_none()).collect();
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    fs::{read_dir, read_to_string, remove_file, File, OpenOptions},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::yahtzee_distributed::{is_worker, WORKER_ID};
+
+// Finished files are recorded in manifest.txt of their tree, one line per file
+// with the path relative to the tree, the size in bytes and the FNV-1a hash as
+// hex. A file that is recomputed gets a new line, the last one counts.
+// Distributed workers each append to their own manifest-<worker>.txt, and all
+// of them together make up the manifest.
+const MANIFEST: &str = "manifest.txt";
+
+static MANIFEST_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn own_manifest() -> String {
+    if is_worker() {
+        format!("manifest-{}.txt", *WORKER_ID)
+    } else {
+        MANIFEST.to_owned()
+    }
+}
+
+fn manifest_paths(tree: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<_> = match read_dir(tree) {
+        Ok(entries) => entries
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                let name = path.file_name().unwrap().to_string_lossy();
+                name.starts_with("manifest") && name.ends_with(".txt")
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    paths
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FileEntry {
+    pub size: u64,
+    pub checksum: u64,
+}
+
+/// Size and 64 bit FNV-1a hash of the file at `path`.
+pub fn file_entry(path: &Path) -> FileEntry {
+    let mut reader =
+        BufReader::with_capacity(1024 * 1024, File::open(path).unwrap());
+    let mut buf = vec![0; 1024 * 1024];
+
+    let mut size = 0;
+    let mut checksum: u64 = 0xcbf29ce484222325;
+
+    loop {
+        let amt = reader.read(&mut buf).unwrap();
+        if amt == 0 {
+            break;
+        }
+
+        for &byte in &buf[..amt] {
+            checksum ^= byte as u64;
+            checksum = checksum.wrapping_mul(0x100000001b3);
+        }
+        size += amt as u64;
+    }
+
+    FileEntry { size, checksum }
+}
+
+pub fn read_manifest(tree: &Path) -> HashMap<String, FileEntry> {
+    manifest_paths(tree)
+        .iter()
+        .filter_map(|path| read_to_string(path).ok())
+        .flat_map(|manifest| {
+            manifest
+                .lines()
+                .filter_map(|line| {
+                    match line.split_whitespace().collect::<Vec<_>>().as_slice()
+                    {
+                        [name, size, checksum] => Some((
+                            name.to_string(),
+                            FileEntry {
+                                size: size.parse().ok()?,
+                                checksum: u64::from_str_radix(checksum, 16)
+                                    .ok()?,
+                            },
+                        )),
+                        // A line cut off by a crash.
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Records the files `names`, relative to `tree`, as finished.
+pub fn record_files(tree: &Path, names: &[String]) {
+    let entries: Vec<_> = names
+        .iter()
+        .map(|name| (name.clone(), file_entry(&tree.join(name))))
+        .collect();
+
+    record_entries(tree, &entries);
+}
+
+/// Records files relative to `tree` with entries that are already known.
+pub fn record_entries(tree: &Path, entries: &[(String, FileEntry)]) {
+    let lines: String = entries
+        .iter()
+        .map(|(name, entry)| {
+            format!("{} {} {:016x}\n", name, entry.size, entry.checksum)
+        })
+        .collect();
+
+    let _lock = MANIFEST_LOCK.lock().unwrap();
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(tree.join(own_manifest()))
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+}
+
+pub fn clear_manifest(tree: &Path) {
+    for path in manifest_paths(tree) {
+        remove_file(path).unwrap();
+    }
+}
+
+/// Whether the file `name` is in `manifest` and still has the recorded size.
+pub fn is_recorded(
+    tree: &Path,
+    manifest: &HashMap<String, FileEntry>,
+    name: &str,
+) -> bool {
+    match (manifest.get(name), tree.join(name).metadata()) {
+        (Some(entry), Ok(metadata)) => entry.size == metadata.len(),
+        _ => false,
+    }
+}
+
+/// Whether the file `name` is in `manifest` and still has the recorded size
+/// and checksum, which means reading all of it.
+pub fn is_intact(
+    tree: &Path,
+    manifest: &HashMap<String, FileEntry>,
+    name: &str,
+) -> bool {
+    is_recorded(tree, manifest, name)
+        && manifest.get(name) == Some(&file_entry(&tree.join(name)))
+}
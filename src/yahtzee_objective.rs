@@ -0,0 +1,115 @@
+use std::{
+    fmt::{self, Display},
+    mem::size_of,
+};
+
+use num_traits::Float;
+
+use crate::yahtzee_strats::RethrowTable;
+
+/// What the free game solver maximizes. The tables hold the certainty
+/// equivalent of the rest of the game, which is the expected score for
+/// `Expected`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Objective {
+    Expected,
+    /// Exponential utility `-exp(-θ * score)`. A positive θ plays it safe and
+    /// a negative θ takes risks.
+    Exponential(f64),
+}
+
+impl Objective {
+    /// Parses `expected`, `exp=<θ>` or `meanvar=<λ>`. Mean minus λ times the
+    /// variance is approximated by exponential utility with θ = 2λ, which has
+    /// the same trade off for small λ and keeps the solution independent of
+    /// the points already scored, so it shares the tables of `exp=<2λ>`.
+    pub fn parse(name: &str) -> Option<Objective> {
+        match name.split_once('=') {
+            None if name == "expected" => Some(Objective::Expected),
+            Some(("exp", theta)) => {
+                theta.parse().ok().map(Objective::Exponential)
+            }
+            Some(("meanvar", lambda)) => lambda
+                .parse()
+                .ok()
+                .map(|l: f64| Objective::Exponential(2.0 * l)),
+            _ => None,
+        }
+    }
+
+    fn theta(&self) -> f64 {
+        match *self {
+            Objective::Expected => 0.0,
+            Objective::Exponential(theta) => theta,
+        }
+    }
+
+    /// Certainty equivalent of each keep in `table`, with `values` indexed
+    /// like the dice of the table.
+    pub fn keep_values<T: Float>(
+        &self,
+        table: &RethrowTable,
+        values: &[T],
+    ) -> Vec<T> {
+        let theta = self.theta();
+
+        if theta == 0.0 {
+            // f32 tables are summed with the f32 weights.
+            let single = size_of::<T>() == size_of::<f32>();
+
+            return table
+                .outcomes
+                .iter()
+                .map(|outcomes| {
+                    outcomes
+                        .iter()
+                        .map(|&(ind, prob, prob_f32)| {
+                            let prob = if single {
+                                T::from(prob_f32)
+                            } else {
+                                T::from(prob)
+                            };
+                            prob.unwrap() * values[ind]
+                        })
+                        .fold(T::zero(), |acc, x| acc + x)
+                })
+                .collect();
+        }
+
+        table
+            .outcomes
+            .iter()
+            .map(|outcomes| {
+                // Shifting by the worst (or best) value keeps the exponents
+                // from overflowing.
+                let shift = outcomes
+                    .iter()
+                    .map(|&(ind, _, _)| values[ind].to_f64().unwrap())
+                    .fold(None, |acc: Option<f64>, v| match acc {
+                        Some(a) if (v - a) * theta >= 0.0 => Some(a),
+                        _ => Some(v),
+                    })
+                    .unwrap();
+
+                let sum: f64 = outcomes
+                    .iter()
+                    .map(|&(ind, prob, _)| {
+                        let value = values[ind].to_f64().unwrap();
+                        prob * (-theta * (value - shift)).exp()
+                    })
+                    .sum();
+
+                T::from(shift - sum.ln() / theta).unwrap()
+            })
+            .collect()
+    }
+}
+
+impl Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Objective::Expected => write!(f, "expected"),
+            Objective::Exponential(theta) => write!(f, "exp={}", theta),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_free_strats::{get_cell_strat, get_rethrow_strat},
+    yahtzee_objective::Objective,
+    yahtzee_strats::{
+        effective_value, find_best_cell, get_dice_index, get_rethrow_table,
+        load_all_tables, load_heuristic_params, HeuristicParams,
+    },
+    yahtzee_target::TargetStrategy,
+    yahtzee_turn_start::{EndgameSolver, TurnStartStrategy},
+};
+
+pub trait Strategy<const N: u64> {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow;
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize;
+}
+
+pub fn load_strategy<const N: u64>(name: Option<&str>) -> Box<dyn Strategy<N>> {
+    match name {
+        None | Some("lookup") => Box::new(LookupStrategy {
+            objective: Objective::Expected,
+        }),
+        Some(name) if name.starts_with("lookup=") => Box::new(LookupStrategy {
+            objective: Objective::parse(&name["lookup=".len()..])
+                .expect("Unknown objective"),
+        }),
+        Some("heuristic") => Box::new(HeuristicStrategy::load::<N>()),
+        Some("turn-start") => Box::new(TurnStartStrategy::load::<N>()),
+        Some("endgame") => Box::new(EndgameSolver::<N>::new()),
+        Some(name) if name.starts_with("target=") => Box::new(
+            TargetStrategy::new(name["target=".len()..].parse().unwrap()),
+        ),
+        Some(name) => panic!("Unknown strategy: {}", name),
+    }
+}
+
+fn free_cells_and_points_above(points: &[Option<u64>]) -> (Vec<bool>, u64) {
+    (
+        points.iter().map(|x| x.is_none()).collect(),
+        points.iter().take(6).filter_map(|x| x.as_ref()).sum(),
+    )
+}
+
+pub struct LookupStrategy {
+    pub objective: Objective,
+}
+
+impl<const N: u64> Strategy<N> for LookupStrategy {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        let (free_cells, points_above) = free_cells_and_points_above(points);
+
+        get_rethrow_strat::<N>(
+            self.objective,
+            &free_cells,
+            dice,
+            throws_left,
+            points_above,
+        )
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        let (free_cells, points_above) = free_cells_and_points_above(points);
+
+        get_cell_strat::<N>(self.objective, &free_cells, dice, points_above)
+    }
+}
+
+#[derive(Clone)]
+pub struct HeuristicStrategy {
+    pub scores: Arc<Vec<Vec<HashMap<DiceThrow, f64>>>>,
+    /// Expected score in each cell of every keep of the rethrow table, by
+    /// throws left after the rethrow and then by cell.
+    pub keep_values: Arc<Vec<Vec<Vec<f64>>>>,
+    pub params: HeuristicParams,
+}
+
+impl HeuristicStrategy {
+    pub fn load<const N: u64>() -> Self {
+        let scores = load_all_tables::<N>().0;
+        let table = get_rethrow_table(N);
+
+        let keep_values = scores
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell_scores| {
+                        let values: Vec<_> =
+                            table.dice.iter().map(|d| cell_scores[d]).collect();
+                        table.keep_values(&values)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            scores: Arc::new(scores),
+            keep_values: Arc::new(keep_values),
+            params: load_heuristic_params::<N>(),
+        }
+    }
+}
+
+impl<const N: u64> Strategy<N> for HeuristicStrategy {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        let (_, points_above) = free_cells_and_points_above(points);
+
+        let keep_values = &self.keep_values[throws_left - 1];
+
+        get_rethrow_table(N).rethrows[get_dice_index::<N>(dice)]
+            .iter()
+            .map(|(sub_throw, keep_ind)| {
+                let value = (0..keep_values.len())
+                    .filter(|&i| points[i].is_none())
+                    .map(|i| {
+                        effective_value::<N>(
+                            keep_values[i][*keep_ind],
+                            points_above,
+                            i,
+                            &self.params,
+                        )
+                    })
+                    .fold(f64::NEG_INFINITY, f64::max);
+
+                (sub_throw, value)
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        find_best_cell::<N>(&self.scores[0], dice, points, &self.params)
+    }
+}
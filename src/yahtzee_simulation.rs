@@ -1,63 +1,67 @@
 use std::time::{Duration, Instant};
 
+use rand::Rng;
+
 use crate::{
-    yahtzee_free_strats::{get_cell_strat, get_rethrow_strat},
-    yahtzee_guide::display_points,
-    yahtzee_strats::new_throw,
-    DiceThrow,
+    yahtzee_guide::display_points, yahtzee_player::Strategy,
+    yahtzee_strats::new_throw, DiceThrow,
 };
 
-fn simulate_game<const N: u64>(
+pub fn simulate_game<const N: u64, S: Strategy<N> + ?Sized, R: Rng>(
+    strategy: &S,
     points: &mut [Option<u64>],
-    free_cells: &mut [bool],
+    rng: &mut R,
 ) {
     for _ in 0..match N {
         5 => 15,
         6 => 20,
         _ => unreachable!(),
     } {
-        let mut dice = DiceThrow::throw(N as usize);
+        simulate_turn::<N, _, _>(strategy, points, rng);
+    }
+}
 
-        let points_above =
-            points.iter().take(6).filter_map(|x| x.as_ref()).sum();
+pub fn simulate_turn<const N: u64, S: Strategy<N> + ?Sized, R: Rng>(
+    strategy: &S,
+    points: &mut [Option<u64>],
+    rng: &mut R,
+) {
+    let mut dice = DiceThrow::throw_with(N as usize, rng);
 
-        for &throws_left in &[2, 1] {
-            let rethrow = get_rethrow_strat::<N>(
-                &free_cells,
-                &dice,
-                throws_left,
-                points_above,
-            );
+    for &throws_left in &[2, 1] {
+        let rethrow = strategy.rethrow(points, &dice, throws_left);
 
-            let th = DiceThrow::throw(rethrow.amt_dice() as usize);
+        let th = DiceThrow::throw_with(rethrow.amt_dice() as usize, rng);
 
-            dice = new_throw(&dice, &rethrow, &th);
-        }
+        dice = new_throw(&dice, &rethrow, &th);
+    }
 
-        let ind = get_cell_strat::<N>(&free_cells, &dice, points_above);
+    let ind = strategy.cell(points, &dice);
 
-        let score = dice.cell_score::<N>(ind);
+    let score = dice.cell_score::<N>(ind);
 
-        points[ind] = Some(score);
-        free_cells[ind] = false;
-    }
+    points[ind] = Some(score);
 }
 
-pub fn simulate_single_game<const N: u64>() {
+pub fn simulate_single_game<const N: u64, S: Strategy<N> + ?Sized>(
+    strategy: &S,
+) {
     let cells = match N {
         5 => 15,
         6 => 20,
         _ => unreachable!(),
     };
     let mut points = vec![None; cells];
-    let mut free_cells = vec![true; cells];
 
-    simulate_game::<N>(&mut points, &mut free_cells);
+    simulate_game::<N, _, _>(strategy, &mut points, &mut rand::thread_rng());
 
     display_points::<_, N>(&points, None, None);
 }
 
-pub fn simulate_multiple<const N: u64>(n: usize) {
+pub fn simulate_multiple<const N: u64, S: Strategy<N> + ?Sized>(
+    strategy: &S,
+    n: usize,
+) {
     let cells = match N {
         5 => 15,
         6 => 20,
@@ -74,7 +78,8 @@ pub fn simulate_multiple<const N: u64>(n: usize) {
         _ => unreachable!(),
     };
     let mut points = vec![None; cells];
-    let mut free_cells = vec![true; cells];
+
+    let mut rng = rand::thread_rng();
 
     let mut averages = vec![Some(0.0); cells];
     let mut avg_bonus = 0.0;
@@ -91,10 +96,7 @@ pub fn simulate_multiple<const N: u64>(n: usize) {
         for x in points.iter_mut() {
             *x = None;
         }
-        for x in free_cells.iter_mut() {
-            *x = true;
-        }
-        simulate_game::<N>(&mut points, &mut free_cells);
+        simulate_game::<N, _, _>(strategy, &mut points, &mut rng);
 
         let bonus = if points
             .iter()
@@ -1,8 +1,13 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{self, create_dir_all, write},
+    path::Path,
 };
 
+use once_cell::sync::Lazy;
+
+use serde::{Deserialize, Serialize};
+
 use super::yahtzee::DiceThrow;
 
 pub struct DiceIter {
@@ -46,6 +51,24 @@ impl Iterator for DiceIter {
     }
 }
 
+static DICE_INDEX: Lazy<Vec<HashMap<DiceThrow, usize>>> = Lazy::new(|| {
+    [5, 6]
+        .iter()
+        .map(|&n| DiceIter::new(n).enumerate().map(|(i, d)| (d, i)).collect())
+        .collect()
+});
+
+/// Position of `dice` in the order of `DiceIter::new(N)`.
+pub fn get_dice_index<const N: u64>(dice: &DiceThrow) -> usize {
+    *DICE_INDEX[match N {
+        5 => 0,
+        6 => 1,
+        _ => unreachable!(),
+    }]
+    .get(dice)
+    .unwrap()
+}
+
 pub fn new_throw(
     orig_dice: &DiceThrow,
     sub_throw: &DiceThrow,
@@ -59,6 +82,14 @@ pub fn new_throw(
     dice
 }
 
+pub fn kept_dice(orig_dice: &DiceThrow, sub_throw: &DiceThrow) -> DiceThrow {
+    let mut dice = orig_dice.clone();
+    for i in 1..=6 {
+        dice[i] -= sub_throw[i];
+    }
+    dice
+}
+
 pub fn re_throw_iters<'a>(
     orig_dice: &'a DiceThrow,
     sub_throw: &'a DiceThrow,
@@ -69,16 +100,198 @@ pub fn re_throw_iters<'a>(
     })
 }
 
+/// Every rethrow of every dice state, with the outcomes of every set of kept
+/// dice, for one amount of dice. Outcomes are in the order of
+/// `re_throw_iters` so sums over them come out the same.
+pub struct RethrowTable {
+    /// The dice states in the order of `DiceIter`.
+    pub dice: Vec<DiceThrow>,
+    /// Every set of kept dice, by amount kept and then in the order of
+    /// `DiceIter`.
+    pub keeps: Vec<DiceThrow>,
+    keep_index: HashMap<DiceThrow, usize>,
+    /// For each dice state, every sub throw with the index of the dice kept,
+    /// in the order of `into_sub_throw_iter`.
+    pub rethrows: Vec<Vec<(DiceThrow, usize)>>,
+    /// For each keep, the index of each outcome with its probability, also
+    /// as f32.
+    pub outcomes: Vec<Vec<(usize, f64, f32)>>,
+}
+
+impl RethrowTable {
+    fn new(n: u64) -> Self {
+        let dice: Vec<_> = DiceIter::new(n).collect();
+        let dice_index: HashMap<_, _> = dice
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.clone(), i))
+            .collect();
+
+        let keeps: Vec<_> = (0..=n).flat_map(DiceIter::new).collect();
+        let keep_index: HashMap<_, _> = keeps
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.clone(), i))
+            .collect();
+
+        let rethrows = dice
+            .iter()
+            .map(|throw| {
+                throw
+                    .clone()
+                    .into_sub_throw_iter()
+                    .map(|sub_throw| {
+                        let ind = keep_index[&kept_dice(throw, &sub_throw)];
+                        (sub_throw, ind)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let nothing = DiceThrow::from([0; 6]);
+
+        let outcomes = keeps
+            .iter()
+            .map(|keep| {
+                DiceIter::new(n - keep.amt_dice())
+                    .map(|new| {
+                        let prob = new.probability();
+                        let ind = dice_index[&new_throw(keep, &nothing, &new)];
+                        (ind, prob, prob as f32)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            dice,
+            keeps,
+            keep_index,
+            rethrows,
+            outcomes,
+        }
+    }
+
+    pub fn get_keep_index(&self, keep: &DiceThrow) -> usize {
+        self.keep_index[keep]
+    }
+
+    /// Expected value of each keep, with `values` indexed like `dice`.
+    pub fn keep_values(&self, values: &[f64]) -> Vec<f64> {
+        self.outcomes
+            .iter()
+            .map(|outcomes| {
+                outcomes
+                    .iter()
+                    .map(|&(ind, prob, _)| prob * values[ind])
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+static RETHROW_TABLES: Lazy<Vec<RethrowTable>> =
+    Lazy::new(|| [5, 6].iter().map(|&n| RethrowTable::new(n)).collect());
+
+pub fn get_rethrow_table(n: u64) -> &'static RethrowTable {
+    &RETHROW_TABLES[match n {
+        5 => 0,
+        6 => 1,
+        _ => unreachable!(),
+    }]
+}
+
 pub fn expected_score(
     orig_dice: &DiceThrow,
     sub_throw: &DiceThrow,
     scores: &HashMap<DiceThrow, f64>,
 ) -> f64 {
-    re_throw_iters(orig_dice, sub_throw)
-        .map(|(throw, prob)| scores.get(&throw).unwrap() * prob)
+    let table = get_rethrow_table(orig_dice.amt_dice());
+
+    table.outcomes[table.get_keep_index(&kept_dice(orig_dice, sub_throw))]
+        .iter()
+        .map(|&(ind, prob, _)| scores.get(&table.dice[ind]).unwrap() * prob)
         .sum()
 }
 
+/// Every rethrow of `orig_dice` with its expected score, best first.
+pub fn ranked_rethrows(
+    orig_dice: &DiceThrow,
+    scores: &HashMap<DiceThrow, f64>,
+) -> Vec<(DiceThrow, f64)> {
+    let mut rethrows: Vec<_> = orig_dice
+        .clone()
+        .into_sub_throw_iter()
+        .map(|sub_throw| {
+            let score = expected_score(orig_dice, &sub_throw, scores);
+            (sub_throw, score)
+        })
+        .collect();
+
+    rethrows.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+
+    rethrows
+}
+
+fn add_distribution(
+    acc: &mut BTreeMap<u64, f64>,
+    dist: BTreeMap<u64, f64>,
+    prob: f64,
+) {
+    for (score, p) in dist {
+        *acc.entry(score).or_insert(0.0) += p * prob;
+    }
+}
+
+fn continued_distribution<const N: u64>(
+    dice: &DiceThrow,
+    throws_left: usize,
+    cell_ind: usize,
+    strats: &Vec<Vec<HashMap<DiceThrow, DiceThrow>>>,
+) -> BTreeMap<u64, f64> {
+    if throws_left == 0 {
+        let mut dist = BTreeMap::new();
+        dist.insert(dice.cell_score::<N>(cell_ind), 1.0);
+        dist
+    } else {
+        score_distribution::<N>(
+            dice,
+            strats[throws_left][cell_ind].get(dice).unwrap(),
+            throws_left,
+            cell_ind,
+            strats,
+        )
+    }
+}
+
+/// Distribution of the final score in `cell_ind` when rethrowing `sub_throw`
+/// with `throws_left` throws left and playing the remaining throws by
+/// `strats`.
+pub fn score_distribution<const N: u64>(
+    orig_dice: &DiceThrow,
+    sub_throw: &DiceThrow,
+    throws_left: usize,
+    cell_ind: usize,
+    strats: &Vec<Vec<HashMap<DiceThrow, DiceThrow>>>,
+) -> BTreeMap<u64, f64> {
+    let mut dist = BTreeMap::new();
+
+    for (throw, prob) in re_throw_iters(orig_dice, sub_throw) {
+        add_distribution(
+            &mut dist,
+            continued_distribution::<N>(
+                &throw,
+                throws_left - 1,
+                cell_ind,
+                strats,
+            ),
+            prob,
+        );
+    }
+
+    dist
+}
+
 pub fn make_score_table<F: Fn(&DiceThrow) -> f64>(
     f: F,
     n: u64,
@@ -208,28 +421,61 @@ pub fn make_all_tables<const N: u64>() -> (
     (scores, strats)
 }
 
-pub fn cache_all_tables<const N: u64>(
-    vals: (
-        Vec<Vec<HashMap<DiceThrow, f64>>>,
-        Vec<Vec<HashMap<DiceThrow, DiceThrow>>>,
-    ),
-) {
-    create_dir_all("bincode/").unwrap();
+type AllTables = (
+    Vec<Vec<HashMap<DiceThrow, f64>>>,
+    Vec<Vec<HashMap<DiceThrow, DiceThrow>>>,
+);
+
+fn write_all_tables<const N: u64>(vals: &AllTables) -> std::io::Result<()> {
+    create_dir_all("bincode/")?;
     write(
         format!("bincode/strats{}.bincode", N),
-        bincode::serialize(&vals).unwrap(),
+        bincode::serialize(vals).unwrap(),
     )
-    .unwrap();
 }
 
-pub fn load_all_tables<const N: u64>() -> (
-    Vec<Vec<HashMap<DiceThrow, f64>>>,
-    Vec<Vec<HashMap<DiceThrow, DiceThrow>>>,
-) {
-    bincode::deserialize(
-        &fs::read(format!("bincode/strats{}.bincode", N)).unwrap(),
-    )
-    .unwrap()
+pub fn cache_all_tables<const N: u64>(vals: AllTables) {
+    write_all_tables::<N>(&vals).unwrap();
+}
+
+fn tables_are_complete<const N: u64>((scores, strats): &AllTables) -> bool {
+    let (amt_cells, amt_dice) = match N {
+        5 => (15, 252),
+        6 => (20, 462),
+        _ => unreachable!(),
+    };
+
+    scores.len() == 3
+        && strats.len() == 3
+        && strats[0].is_empty()
+        && scores.iter().all(|tables| {
+            tables.len() == amt_cells
+                && tables.iter().all(|table| table.len() == amt_dice)
+        })
+        && strats[1..].iter().all(|tables| {
+            tables.len() == amt_cells
+                && tables.iter().all(|table| table.len() == amt_dice)
+        })
+}
+
+// The tables only take a moment to compute, so a missing or broken cache is
+// rebuilt in memory and written back if the bincode directory is writable.
+pub fn load_all_tables<const N: u64>() -> AllTables {
+    if let Some(vals) = fs::read(format!("bincode/strats{}.bincode", N))
+        .ok()
+        .and_then(|bytes| bincode::deserialize::<AllTables>(&bytes).ok())
+        .filter(tables_are_complete::<N>)
+    {
+        return vals;
+    }
+
+    let vals = make_all_tables::<N>();
+
+    if let Err(e) = write_all_tables::<N>(&vals) {
+        eprintln!("Could not cache strats for {} dice: {}", N, e);
+    }
+
+    vals
 }
 
 pub fn get_yahtzee_index<const N: u64>(name: &str) -> usize {
@@ -291,60 +537,116 @@ pub fn get_index_name<const N: u64>(ind: usize) -> &'static str {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeuristicParams {
+    pub bonus_bias: f64,
+    pub bonus_offset: f64,
+    pub bonus_objective: f64,
+    pub chance_penalty: f64,
+}
+
+impl HeuristicParams {
+    pub fn default_for<const N: u64>() -> Self {
+        match N {
+            5 => Self {
+                bonus_bias: 1.0,
+                bonus_offset: 1.0,
+                bonus_objective: 3.0,
+                chance_penalty: 50.0,
+            },
+            6 => Self {
+                bonus_bias: 1.5,
+                bonus_offset: 1.5,
+                bonus_objective: 4.0,
+                chance_penalty: 100.0,
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+pub fn cache_heuristic_params<const N: u64>(params: &HeuristicParams) {
+    create_dir_all("bincode/").unwrap();
+    write(
+        format!("bincode/heuristic{}.bincode", N),
+        bincode::serialize(params).unwrap(),
+    )
+    .unwrap();
+}
+
+pub fn clear_heuristic_params<const N: u64>() {
+    let path = format!("bincode/heuristic{}.bincode", N);
+    if Path::new(&path).exists() {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+pub fn load_heuristic_params<const N: u64>() -> HeuristicParams {
+    let path = format!("bincode/heuristic{}.bincode", N);
+
+    match fs::read(&path).map(|bytes| bincode::deserialize(&bytes)) {
+        Ok(Ok(params)) => params,
+        Ok(Err(_)) => {
+            println!("Could not read {}, using the default parameters.", path);
+            HeuristicParams::default_for::<N>()
+        }
+        Err(_) => HeuristicParams::default_for::<N>(),
+    }
+}
+
 pub fn effective_score<const N: u64>(
-    scores: &Vec<HashMap<DiceThrow, f64>>,
+    scores: &[HashMap<DiceThrow, f64>],
     throw: &DiceThrow,
     points_above: u64,
     cell_ind: usize,
+    params: &HeuristicParams,
 ) -> f64 {
-    let bonus_bias = match N {
-        5 => 1.0,
-        6 => 1.5,
-        _ => unreachable!(),
-    };
-
-    let bonus_offset = match N {
-        5 => 1.0,
-        6 => 1.5,
-        _ => unreachable!(),
-    };
-
-    let bonus_objective = (cell_ind + 1) as f64
-        * match N {
-            5 => 3.0,
-            6 => 4.0,
-            _ => unreachable!(),
-        };
+    effective_value::<N>(
+        *scores[cell_ind].get(throw).unwrap(),
+        points_above,
+        cell_ind,
+        params,
+    )
+}
 
-    let score = scores[cell_ind].get(throw).unwrap();
+pub fn effective_value<const N: u64>(
+    score: f64,
+    points_above: u64,
+    cell_ind: usize,
+    params: &HeuristicParams,
+) -> f64 {
+    let bonus_objective = (cell_ind + 1) as f64 * params.bonus_objective;
 
     score
         * if cell_ind <= 5 {
-            (score + points_above as f64 - bonus_objective + bonus_offset)
-                * bonus_bias
+            (score + points_above as f64 - bonus_objective
+                + params.bonus_offset)
+                * params.bonus_bias
         } else {
             1.0
         }
         - if matches!((N, cell_ind), (5, 13) | (6, 18)) {
-            match N {
-                5 => 50.0,
-                6 => 100.0,
-                _ => unreachable!(),
-            }
+            params.chance_penalty
         } else {
             0.0
         }
 }
 
 pub fn find_best_cell<const N: u64>(
-    scores: &Vec<HashMap<DiceThrow, f64>>,
+    scores: &[HashMap<DiceThrow, f64>],
     throw: &DiceThrow,
     points: &[Option<u64>],
+    params: &HeuristicParams,
 ) -> usize {
     let points_above = points.iter().take(6).filter_map(|x| x.as_ref()).sum();
     let ind = (0..scores.len())
         .filter(|&i| points[i].is_none())
-        .map(|i| (i, effective_score::<N>(scores, throw, points_above, i)))
+        .map(|i| {
+            (
+                i,
+                effective_score::<N>(scores, throw, points_above, i, params),
+            )
+        })
         .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
         .unwrap()
         .0;
@@ -0,0 +1,508 @@
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, read_to_string, write, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread::spawn,
+    time::{Duration, Instant},
+};
+
+use num_integer::binomial;
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_free_strats::{
+        amt_cells, amt_points_above, is_reachable, LOOKUP_PATH, NUM_CPUS,
+    },
+    yahtzee_guide::get_total_score,
+    yahtzee_player::Strategy,
+    yahtzee_strats::{get_rethrow_table, DiceIter},
+    yahtzee_turn::{solve_turn, CachedTurn, TurnSolution},
+    yahtzee_turn_start::{
+        bonus_amount, bonus_threshold, cells_to_mask, fill_cell, mask_to_cells,
+    },
+};
+
+enum Message {
+    Progress(usize),
+    Done,
+}
+
+/// Highest possible total of a game.
+pub fn max_total<const N: u64>() -> usize {
+    (0..amt_cells::<N>())
+        .map(|i| {
+            DiceIter::new(N)
+                .map(|dice| dice.cell_score::<N>(i))
+                .max()
+                .unwrap()
+        })
+        .sum::<u64>() as usize
+        + bonus_amount::<N>() as usize
+}
+
+// Every table entry holds the chance of reaching each amount of points still
+// needed, from 0 up to the highest total.
+fn amt_needed<const N: u64>() -> usize {
+    max_total::<N>() + 1
+}
+
+fn entry_len<const N: u64>() -> usize {
+    amt_points_above::<N>() * amt_needed::<N>()
+}
+
+// Position of `mask` among the masks with as many free cells, in increasing
+// order.
+fn mask_rank(mask: usize) -> usize {
+    (0..usize::BITS as usize)
+        .filter(|&i| mask & (1 << i) != 0)
+        .enumerate()
+        .map(|(j, i)| binomial(i, j + 1))
+        .sum()
+}
+
+fn layer_masks<const N: u64>(free_cells: u32) -> Vec<usize> {
+    (0..1usize << amt_cells::<N>())
+        .filter(|mask| mask.count_ones() == free_cells)
+        .collect()
+}
+
+fn target_path<const N: u64>() -> PathBuf {
+    Path::new(&*LOOKUP_PATH).join(format!("{}/target/", N))
+}
+
+fn layer_path<const N: u64>(free_cells: u32) -> PathBuf {
+    target_path::<N>().join(format!("{}.bin", free_cells))
+}
+
+// Only the points above that can be reached with the cells of `mask` filled
+// are stored, as rows of `amt_needed` chances.
+fn stored_points_above<const N: u64>(mask: usize) -> Vec<usize> {
+    let cells = mask_to_cells::<N>(mask);
+
+    (0..amt_points_above::<N>())
+        .filter(|&points_above| is_reachable::<N>(&cells, points_above))
+        .collect()
+}
+
+type RowOffsets = Arc<Vec<usize>>;
+
+static ROW_OFFSETS: Lazy<Mutex<HashMap<(u64, u32), RowOffsets>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// The first row of each mask in the layer file with `free_cells` free, by
+// rank, and the amount of rows in the file at the end.
+fn row_offsets<const N: u64>(free_cells: u32) -> Arc<Vec<usize>> {
+    ROW_OFFSETS
+        .lock()
+        .unwrap()
+        .entry((N, free_cells))
+        .or_insert_with(|| {
+            // Increasing masks are in the order of their rank.
+            let mut offsets = vec![0];
+            for mask in layer_masks::<N>(free_cells) {
+                let rows = stored_points_above::<N>(mask).len();
+                offsets.push(offsets.last().unwrap() + rows);
+            }
+
+            Arc::new(offsets)
+        })
+        .clone()
+}
+
+fn layer_len<const N: u64>(free_cells: u32) -> u64 {
+    (row_offsets::<N>(free_cells).last().unwrap() * amt_needed::<N>() * 4)
+        as u64
+}
+
+// Writes the rows of `mask` at their place in `file`.
+fn write_rows<const N: u64>(file: &Mutex<File>, mask: usize, rows: &[f32]) {
+    let offset = row_offsets::<N>(mask.count_ones())[mask_rank(mask)];
+    let bytes: Vec<u8> = rows.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+    let mut file = file.lock().unwrap();
+    file.seek(SeekFrom::Start((offset * amt_needed::<N>() * 4) as u64))
+        .unwrap();
+    file.write_all(&bytes).unwrap();
+}
+
+/// Chance of getting at least each amount of points with `mask` free and
+/// `points_above` reached before the first throw of the turn, given the same
+/// for every state the turn can lead to in `next`. The chances are indexed by
+/// the points needed.
+pub fn solve_target_turn<'a, const N: u64, F: Fn(usize) -> &'a [f32]>(
+    mask: usize,
+    points_above: u64,
+    next: F,
+) -> Vec<f32> {
+    let table = get_rethrow_table(N);
+    let amt_needed = amt_needed::<N>();
+
+    let free: Vec<_> = (0..amt_cells::<N>())
+        .filter(|&i| mask & (1 << i) != 0)
+        .map(|i| (i, next(mask & !(1 << i))))
+        .collect();
+
+    let mut values: Vec<Vec<f64>> = table
+        .dice
+        .iter()
+        .map(|dice| {
+            let mut best = vec![0.0f64; amt_needed];
+
+            for &(cell_ind, chances) in &free {
+                let (gain, n_points_above) = fill_cell::<N>(
+                    cell_ind,
+                    dice.cell_score::<N>(cell_ind),
+                    points_above,
+                );
+                let chances = &chances[n_points_above as usize * amt_needed..];
+
+                for (needed, b) in best.iter_mut().enumerate() {
+                    let chance =
+                        chances[needed.saturating_sub(gain as usize)] as f64;
+                    if chance > *b {
+                        *b = chance;
+                    }
+                }
+            }
+
+            best
+        })
+        .collect();
+
+    for _ in 0..2 {
+        let keep_values: Vec<Vec<f64>> = table
+            .outcomes
+            .iter()
+            .map(|outcomes| {
+                let mut acc = vec![0.0; amt_needed];
+                for &(ind, prob, _) in outcomes {
+                    for (a, v) in acc.iter_mut().zip(values[ind].iter()) {
+                        *a += prob * v;
+                    }
+                }
+                acc
+            })
+            .collect();
+
+        values = table
+            .rethrows
+            .iter()
+            .map(|rethrows| {
+                let mut best = vec![0.0f64; amt_needed];
+                for (_, keep_ind) in rethrows {
+                    for (b, &v) in best.iter_mut().zip(&keep_values[*keep_ind])
+                    {
+                        if v > *b {
+                            *b = v;
+                        }
+                    }
+                }
+                best
+            })
+            .collect();
+    }
+
+    let mut start = vec![0.0; amt_needed];
+    for (dice, values) in table.dice.iter().zip(values.iter()) {
+        let prob = dice.probability();
+        for (s, v) in start.iter_mut().zip(values.iter()) {
+            *s += prob * v;
+        }
+    }
+
+    start.into_iter().map(|x| x as f32).collect()
+}
+
+fn make_layer<const N: u64>(free_cells: u32) {
+    let masks = Arc::new(layer_masks::<N>(free_cells));
+
+    let n = *row_offsets::<N>(free_cells).last().unwrap();
+
+    let file = Arc::new(Mutex::new(
+        File::create(layer_path::<N>(free_cells)).unwrap(),
+    ));
+
+    let (message_s, message_r) = crossbeam_channel::unbounded();
+
+    let handles: Vec<_> = (0..*NUM_CPUS)
+        .map(|t| {
+            let masks = masks.clone();
+            let file = file.clone();
+            let message_s = message_s.clone();
+            spawn(move || {
+                let mut count = 0;
+                let mut timer = Instant::now();
+                for &mask in masks.iter().skip(t).step_by(*NUM_CPUS) {
+                    // The layer below is read from disk one mask at a time.
+                    let next: Vec<_> = (0..amt_cells::<N>())
+                        .filter(|&i| mask & (1 << i) != 0)
+                        .map(|i| {
+                            let next_mask = mask & !(1 << i);
+                            (next_mask, load_target_entry::<N>(next_mask))
+                        })
+                        .collect();
+
+                    let mut rows = Vec::new();
+
+                    for points_above in stored_points_above::<N>(mask) {
+                        rows.extend(solve_target_turn::<N, _>(
+                            mask,
+                            points_above as u64,
+                            |next_mask| {
+                                &next
+                                    .iter()
+                                    .find(|(m, _)| *m == next_mask)
+                                    .unwrap()
+                                    .1
+                            },
+                        ));
+
+                        count += 1;
+                        if timer.elapsed() >= Duration::from_secs(1) {
+                            timer += Duration::from_secs(1);
+                            message_s.send(Message::Progress(count)).unwrap();
+                            count = 0;
+                        }
+                    }
+
+                    write_rows::<N>(&file, mask, &rows);
+                }
+                message_s.send(Message::Progress(count)).unwrap();
+                message_s.send(Message::Done).unwrap();
+            })
+        })
+        .collect();
+
+    let mut amt_done = 0;
+    let mut i = 0;
+    let mut timer = Instant::now();
+
+    while amt_done < *NUM_CPUS {
+        if let Ok(message) = message_r.recv_timeout(Duration::from_millis(100))
+        {
+            match message {
+                Message::Progress(count) => i += count,
+                Message::Done => amt_done += 1,
+            }
+        }
+
+        if timer.elapsed() >= Duration::from_secs(1) {
+            timer += Duration::from_secs(1);
+            println!("{} / {} = {:.2}%", i, n, (i as f32) / (n as f32) * 100.0);
+        }
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// Computes, for every set of free cells and points above, the chance of
+/// getting at least each amount of points in the rest of the game, one layer
+/// of free cells at a time. Each layer is written to disk as it is computed,
+/// and read back from there for the next one.
+pub fn compute_target_tables<const N: u64>() {
+    let timer = Instant::now();
+
+    let path = target_path::<N>();
+    create_dir_all(&path).unwrap();
+
+    // Tables from before the unreachable points above were left out are
+    // started over, which shows in the size of the last layer.
+    let done = read_to_string(path.join("done.txt"))
+        .ok()
+        .map(|done| done.trim().parse::<u32>().unwrap())
+        .filter(|&done| {
+            layer_path::<N>(done).metadata().map(|m| m.len()).ok()
+                == Some(layer_len::<N>(done))
+        });
+
+    let done = match done {
+        Some(done) => done,
+        None => {
+            // With no cells free the points needed have to be reached already.
+            let mut rows = Vec::new();
+            for _ in stored_points_above::<N>(0) {
+                let mut row = vec![0.0f32; amt_needed::<N>()];
+                row[0] = 1.0;
+                rows.extend(row);
+            }
+            let file = Mutex::new(File::create(layer_path::<N>(0)).unwrap());
+            write_rows::<N>(&file, 0, &rows);
+            write(path.join("done.txt"), "0").unwrap();
+            0
+        }
+    };
+
+    for free_cells in (done + 1)..=amt_cells::<N>() as u32 {
+        println!("Computing target chances for {} free cells:", free_cells);
+
+        let layer_timer = Instant::now();
+
+        make_layer::<N>(free_cells);
+
+        write(path.join("done.txt"), format!("{}", free_cells)).unwrap();
+
+        println!("took {:?}\n", layer_timer.elapsed());
+    }
+
+    let start = load_target_entry::<N>((1 << amt_cells::<N>()) - 1);
+
+    println!(
+        "Chance of reaching 200, 250 and 300 in a new game: {}, {}, {}",
+        start[200], start[250], start[300]
+    );
+    println!("Total time: {:?}", timer.elapsed());
+}
+
+/// The chances of reaching each amount of points needed with `mask` free,
+/// indexed by points above and then points needed. Points above that can not
+/// be reached have no chance of anything.
+pub fn load_target_entry<const N: u64>(mask: usize) -> Vec<f32> {
+    let stored = stored_points_above::<N>(mask);
+    let offset = row_offsets::<N>(mask.count_ones())[mask_rank(mask)];
+
+    let mut file = File::open(layer_path::<N>(mask.count_ones())).unwrap();
+
+    file.seek(SeekFrom::Start((offset * amt_needed::<N>() * 4) as u64))
+        .unwrap();
+
+    let mut bytes = vec![0; stored.len() * amt_needed::<N>() * 4];
+    file.read_exact(&mut bytes).unwrap();
+
+    let mut entry = vec![0.0; entry_len::<N>()];
+
+    for (row, points_above) in stored.into_iter().enumerate() {
+        for needed in 0..amt_needed::<N>() {
+            let b = &bytes[(row * amt_needed::<N>() + needed) * 4..];
+            entry[points_above * amt_needed::<N>() + needed] =
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+    }
+
+    entry
+}
+
+/// Chance of a total of at least `score` from a state with `total` points and
+/// `chances` of reaching each amount of points still needed.
+pub fn chance_at_least(chances: &[f32], total: u64, score: u64) -> f64 {
+    let needed = score.saturating_sub(total) as usize;
+
+    if needed < chances.len() {
+        chances[needed] as f64
+    } else {
+        0.0
+    }
+}
+
+/// Solves the turn from `points` when ending it in a state is worth `value`
+/// of the chances of reaching each amount of points from there and the total
+/// after the turn.
+pub fn solve_turn_from_chances<const N: u64, F: Fn(&[f32], u64) -> f64>(
+    points: &[Option<u64>],
+    value: F,
+) -> TurnSolution {
+    let cells: Vec<_> = points.iter().map(|x| x.is_none()).collect();
+    let mask = cells_to_mask(&cells);
+    let points_above = capped_points_above::<N>(points);
+
+    let next: Vec<_> = (0..cells.len())
+        .map(|i| {
+            if cells[i] {
+                load_target_entry::<N>(mask & !(1 << i))
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+
+    solve_turn::<N, _>(&cells, |cell_ind, score| {
+        let (_, n_points_above) = fill_cell::<N>(cell_ind, score, points_above);
+        let start = n_points_above as usize * amt_needed::<N>();
+
+        let mut n_points = points.to_vec();
+        n_points[cell_ind] = Some(score);
+
+        value(
+            &next[cell_ind][start..start + amt_needed::<N>()],
+            get_total_score::<N>(&n_points),
+        )
+    })
+}
+
+pub fn capped_points_above<const N: u64>(points: &[Option<u64>]) -> u64 {
+    points
+        .iter()
+        .take(6)
+        .filter_map(|x| x.as_ref())
+        .sum::<u64>()
+        .min(bonus_threshold::<N>())
+}
+
+/// The free cells, capped points above and total of a sheet, which a turn
+/// solved from the chances depends on.
+pub type SolutionKey = (usize, u64, u64);
+
+pub fn solution_key<const N: u64>(points: &[Option<u64>]) -> SolutionKey {
+    let cells: Vec<_> = points.iter().map(|x| x.is_none()).collect();
+
+    (
+        cells_to_mask(&cells),
+        capped_points_above::<N>(points),
+        get_total_score::<N>(points),
+    )
+}
+
+/// Plays to maximize the chance of the total reaching `target`.
+pub struct TargetStrategy {
+    pub target: u64,
+    solution: CachedTurn<SolutionKey>,
+}
+
+impl TargetStrategy {
+    pub fn new(target: u64) -> Self {
+        Self {
+            target,
+            solution: CachedTurn::new(),
+        }
+    }
+
+    pub fn with_solution<const N: u64, T, F: FnOnce(&TurnSolution) -> T>(
+        &self,
+        points: &[Option<u64>],
+        f: F,
+    ) -> T {
+        self.solution.with(
+            solution_key::<N>(points),
+            |_| {
+                solve_turn_from_chances::<N, _>(points, |chances, total| {
+                    chance_at_least(chances, total, self.target)
+                })
+            },
+            f,
+        )
+    }
+}
+
+impl<const N: u64> Strategy<N> for TargetStrategy {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.rethrow::<N>(dice, throws_left).clone()
+        })
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.cell::<N>(dice)
+        })
+    }
+}
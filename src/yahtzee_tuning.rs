@@ -0,0 +1,154 @@
+use std::{thread::spawn, time::Instant};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    yahtzee_free_strats::NUM_CPUS,
+    yahtzee_guide::get_total_score,
+    yahtzee_player::HeuristicStrategy,
+    yahtzee_simulation::simulate_game,
+    yahtzee_strats::{
+        cache_heuristic_params, clear_heuristic_params, HeuristicParams,
+    },
+};
+
+const PARAM_NAMES: [&str; 4] = [
+    "bonus_bias",
+    "bonus_offset",
+    "bonus_objective",
+    "chance_penalty",
+];
+
+fn param_mut(params: &mut HeuristicParams, ind: usize) -> &mut f64 {
+    match ind {
+        0 => &mut params.bonus_bias,
+        1 => &mut params.bonus_offset,
+        2 => &mut params.bonus_objective,
+        3 => &mut params.chance_penalty,
+        _ => unreachable!(),
+    }
+}
+
+// Game i is always played with the rng seeded by seed + i, so two parameter
+// sets evaluated with the same seed see the same dice as long as they make
+// the same decisions (common random numbers).
+fn average_score<const N: u64>(
+    strategy: &HeuristicStrategy,
+    params: &HeuristicParams,
+    games: usize,
+    seed: u64,
+) -> f64 {
+    let threads = *NUM_CPUS;
+
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let strategy = HeuristicStrategy {
+                scores: strategy.scores.clone(),
+                keep_values: strategy.keep_values.clone(),
+                params: params.clone(),
+            };
+            spawn(move || {
+                let mut points = vec![None; strategy.scores[0].len()];
+                (t..games)
+                    .step_by(threads)
+                    .map(|i| {
+                        let mut rng =
+                            StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+                        for x in points.iter_mut() {
+                            *x = None;
+                        }
+                        simulate_game::<N, _, _>(
+                            &strategy,
+                            &mut points,
+                            &mut rng,
+                        );
+                        get_total_score::<N>(&points)
+                    })
+                    .sum::<u64>()
+            })
+        })
+        .collect();
+
+    handles.into_iter().map(|h| h.join().unwrap()).sum::<u64>() as f64
+        / games as f64
+}
+
+pub fn tune<const N: u64>(games: usize, rounds: usize) {
+    let strategy = HeuristicStrategy::load::<N>();
+
+    let seed: u64 = rand::random();
+    println!(
+        "Tuning with {} games per evaluation, seed = {}",
+        games, seed
+    );
+
+    let timer = Instant::now();
+
+    let mut best = strategy.params.clone();
+    let mut best_score = average_score::<N>(&strategy, &best, games, seed);
+
+    println!("start: {:.3} with {:?}", best_score, best);
+
+    let mut steps = [0.25, 0.5, 0.5, N as f64 * 2.0];
+
+    for round in 0..rounds {
+        let mut improved = false;
+
+        for (ind, &step) in steps.iter().enumerate() {
+            for &dir in &[1.0, -1.0] {
+                let mut candidate = best.clone();
+                *param_mut(&mut candidate, ind) += dir * step;
+
+                let score =
+                    average_score::<N>(&strategy, &candidate, games, seed);
+
+                if score > best_score {
+                    println!(
+                        "  {} -> {:.3}: {:.3}",
+                        PARAM_NAMES[ind],
+                        *param_mut(&mut candidate, ind),
+                        score
+                    );
+                    best = candidate;
+                    best_score = score;
+                    improved = true;
+                }
+            }
+        }
+
+        if !improved {
+            for step in steps.iter_mut() {
+                *step /= 2.0;
+            }
+        }
+
+        println!(
+            "round {} / {}: {:.3}    took {:?}",
+            round + 1,
+            rounds,
+            best_score,
+            timer.elapsed()
+        );
+    }
+
+    // The search overfits to the dice of its seed, so compare against the
+    // defaults on a fresh set of games before reporting.
+    let check_seed: u64 = rand::random();
+    let default_score = average_score::<N>(
+        &strategy,
+        &HeuristicParams::default_for::<N>(),
+        games,
+        check_seed,
+    );
+    let tuned_score = average_score::<N>(&strategy, &best, games, check_seed);
+
+    println!("defaults: {:.3}", default_score);
+    println!("tuned:    {:.3} with {:?}", tuned_score, best);
+
+    if tuned_score > default_score {
+        cache_heuristic_params::<N>(&best);
+    } else {
+        clear_heuristic_params::<N>();
+        println!("The tuned parameters are no better, kept the defaults");
+    }
+}
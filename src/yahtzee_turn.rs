@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_strats::{get_dice_index, get_rethrow_table, DiceIter},
+};
+
+/// Optimal play of a single turn against a fixed value for every
+/// (cell, score) pair. `values` and `rethrows` are indexed by throws left and
+/// then by the index of the dice in `DiceIter::new(N)`.
+pub struct TurnSolution {
+    pub values: Vec<Vec<f64>>,
+    pub rethrows: Vec<Vec<DiceThrow>>,
+    pub cells: Vec<usize>,
+}
+
+impl TurnSolution {
+    pub fn value<const N: u64>(
+        &self,
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> f64 {
+        self.values[throws_left][get_dice_index::<N>(dice)]
+    }
+
+    pub fn rethrow<const N: u64>(
+        &self,
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> &DiceThrow {
+        &self.rethrows[throws_left][get_dice_index::<N>(dice)]
+    }
+
+    pub fn cell<const N: u64>(&self, dice: &DiceThrow) -> usize {
+        self.cells[get_dice_index::<N>(dice)]
+    }
+
+    /// Expected value of the turn before the first throw.
+    pub fn start_value<const N: u64>(&self) -> f64 {
+        DiceIter::new(N)
+            .zip(self.values[2].iter())
+            .map(|(dice, value)| dice.probability() * value)
+            .sum()
+    }
+}
+
+/// The solution of the last turn asked about, with the key of the state it
+/// was solved for. Strategies are asked about the same turn up to three times,
+/// so it is only solved again when the key changes.
+pub struct CachedTurn<K> {
+    solution: RefCell<Option<(K, TurnSolution)>>,
+}
+
+impl<K> Default for CachedTurn<K> {
+    fn default() -> Self {
+        Self {
+            solution: RefCell::new(None),
+        }
+    }
+}
+
+impl<K: PartialEq> CachedTurn<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `f` with the solution of the turn at `key`, which is solved by
+    /// `solve` unless it was the last one.
+    pub fn with<T, S, F>(&self, key: K, solve: S, f: F) -> T
+    where
+        S: FnOnce(&K) -> TurnSolution,
+        F: FnOnce(&TurnSolution) -> T,
+    {
+        if !matches!(&*self.solution.borrow(), Some((k, _)) if *k == key) {
+            let solution = solve(&key);
+            *self.solution.borrow_mut() = Some((key, solution));
+        }
+
+        f(&self.solution.borrow().as_ref().unwrap().1)
+    }
+}
+
+pub fn solve_turn<const N: u64, F: Fn(usize, u64) -> f64>(
+    cells: &[bool],
+    value: F,
+) -> TurnSolution {
+    let mut best_cells = Vec::new();
+    let mut final_values = Vec::new();
+
+    for dice in DiceIter::new(N) {
+        let (cell_ind, score) = (0..cells.len())
+            .filter(|&i| cells[i])
+            .map(|i| (i, value(i, dice.cell_score::<N>(i))))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+
+        best_cells.push(cell_ind);
+        final_values.push(score);
+    }
+
+    let mut values = vec![final_values];
+    let mut rethrows = vec![Vec::new()];
+
+    let table = get_rethrow_table(N);
+
+    for throws_left in 1..=2 {
+        // The value of a rethrow only depends on the dice that are kept.
+        let keep_values = table.keep_values(&values[throws_left - 1]);
+
+        let (layer_rethrows, layer_values) = table
+            .rethrows
+            .iter()
+            .map(|rethrows| {
+                rethrows
+                    .iter()
+                    .map(|(sub_throw, keep_ind)| {
+                        (sub_throw.clone(), keep_values[*keep_ind])
+                    })
+                    .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                    .unwrap()
+            })
+            .unzip();
+
+        rethrows.push(layer_rethrows);
+        values.push(layer_values);
+    }
+
+    TurnSolution {
+        values,
+        rethrows,
+        cells: best_cells,
+    }
+}
@@ -0,0 +1,439 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{create_dir_all, read, read_to_string, write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::spawn,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_free_strats::{amt_cells, amt_points_above, LOOKUP_PATH, NUM_CPUS},
+    yahtzee_player::Strategy,
+    yahtzee_strats::DiceIter,
+    yahtzee_turn::{solve_turn, CachedTurn, TurnSolution},
+};
+
+enum Message {
+    Progress(usize),
+    Done(Vec<(usize, f32)>),
+}
+
+pub fn bonus_threshold<const N: u64>() -> u64 {
+    match N {
+        5 => 63,
+        6 => 84,
+        _ => unreachable!(),
+    }
+}
+
+pub fn bonus_amount<const N: u64>() -> u64 {
+    match N {
+        5 => 50,
+        6 => 100,
+        _ => unreachable!(),
+    }
+}
+
+/// Points gained and the new capped points above from putting `score` in
+/// `cell_ind`. The bonus is counted once, the turn the threshold is reached,
+/// like `get_total_score` and the lookup tables.
+pub fn fill_cell<const N: u64>(
+    cell_ind: usize,
+    score: u64,
+    points_above: u64,
+) -> (u64, u64) {
+    if cell_ind < 6 {
+        let bonus = if points_above < bonus_threshold::<N>()
+            && points_above + score >= bonus_threshold::<N>()
+        {
+            bonus_amount::<N>()
+        } else {
+            0
+        };
+
+        (
+            score + bonus,
+            (points_above + score).min(bonus_threshold::<N>()),
+        )
+    } else {
+        (score, points_above)
+    }
+}
+
+pub fn cells_to_mask(cells: &[bool]) -> usize {
+    cells
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b)
+        .map(|(i, _)| 1 << i)
+        .sum()
+}
+
+pub fn mask_to_cells<const N: u64>(mask: usize) -> Vec<bool> {
+    (0..amt_cells::<N>())
+        .map(|i| mask & (1 << i) != 0)
+        .collect()
+}
+
+fn value_index<const N: u64>(mask: usize, points_above: u64) -> usize {
+    mask * amt_points_above::<N>() + points_above as usize
+}
+
+/// Solves the turn at the start of which `mask` is free and `points_above`
+/// is reached, with each choice valued by its points plus the turn start
+/// value of the state it leads to.
+pub fn solve_turn_from_values<const N: u64>(
+    values: &[f32],
+    mask: usize,
+    points_above: u64,
+) -> TurnSolution {
+    solve_turn::<N, _>(&mask_to_cells::<N>(mask), |cell_ind, score| {
+        let (gain, n_points_above) =
+            fill_cell::<N>(cell_ind, score, points_above);
+
+        gain as f64
+            + values[value_index::<N>(mask & !(1 << cell_ind), n_points_above)]
+                as f64
+    })
+}
+
+fn turn_start_path<const N: u64>() -> PathBuf {
+    Path::new(&*LOOKUP_PATH).join(format!("{}/turn_start/", N))
+}
+
+fn make_layer<const N: u64>(
+    values: &Arc<Vec<f32>>,
+    free_cells: u32,
+) -> Vec<(usize, f32)> {
+    let masks: Arc<Vec<usize>> = Arc::new(
+        (0..1usize << amt_cells::<N>())
+            .filter(|mask| mask.count_ones() == free_cells)
+            .collect(),
+    );
+
+    let n = masks.len() * amt_points_above::<N>();
+
+    let (message_s, message_r) = crossbeam_channel::unbounded();
+
+    let handles: Vec<_> = (0..*NUM_CPUS)
+        .map(|t| {
+            let values = values.clone();
+            let masks = masks.clone();
+            let message_s = message_s.clone();
+            spawn(move || {
+                let mut results = Vec::new();
+                let mut count = 0;
+                let mut timer = Instant::now();
+                for &mask in masks.iter().skip(t).step_by(*NUM_CPUS) {
+                    for points_above in 0..amt_points_above::<N>() as u64 {
+                        let solution = solve_turn_from_values::<N>(
+                            &values,
+                            mask,
+                            points_above,
+                        );
+                        results.push((
+                            value_index::<N>(mask, points_above),
+                            solution.start_value::<N>() as f32,
+                        ));
+
+                        count += 1;
+                        if timer.elapsed() >= Duration::from_secs(1) {
+                            timer += Duration::from_secs(1);
+                            message_s.send(Message::Progress(count)).unwrap();
+                            count = 0;
+                        }
+                    }
+                }
+                message_s.send(Message::Progress(count)).unwrap();
+                message_s.send(Message::Done(results)).unwrap();
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    let mut amt_done = 0;
+    let mut i = 0;
+    let mut timer = Instant::now();
+
+    while amt_done < *NUM_CPUS {
+        if let Ok(message) = message_r.recv_timeout(Duration::from_millis(100))
+        {
+            match message {
+                Message::Progress(count) => i += count,
+                Message::Done(mut r) => {
+                    results.append(&mut r);
+                    amt_done += 1;
+                }
+            }
+        }
+
+        if timer.elapsed() >= Duration::from_secs(1) {
+            timer += Duration::from_secs(1);
+            println!("{} / {} = {:.2}%", i, n, (i as f32) / (n as f32) * 100.0);
+        }
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    results
+}
+
+pub fn compute_turn_start_values<const N: u64>() {
+    let timer = Instant::now();
+
+    let path = turn_start_path::<N>();
+    create_dir_all(&path).unwrap();
+
+    let amt_values = value_index::<N>(1 << amt_cells::<N>(), 0);
+
+    let (mut values, done) = match read_to_string(path.join("done.txt")) {
+        Ok(done) => (
+            load_turn_start_values::<N>(),
+            done.trim().parse::<u32>().unwrap(),
+        ),
+        Err(_) => (vec![0.0; amt_values], 0),
+    };
+
+    for free_cells in (done + 1)..=amt_cells::<N>() as u32 {
+        println!("Computing turn start values for {} free cells:", free_cells);
+
+        let layer_timer = Instant::now();
+
+        let shared = Arc::new(values);
+        let results = make_layer::<N>(&shared, free_cells);
+        values = Arc::try_unwrap(shared).unwrap();
+
+        for (ind, value) in results {
+            values[ind] = value;
+        }
+
+        let bytes: Vec<u8> =
+            values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        write(path.join("values.bin"), bytes).unwrap();
+        write(path.join("done.txt"), format!("{}", free_cells)).unwrap();
+
+        println!("took {:?}\n", layer_timer.elapsed());
+    }
+
+    println!(
+        "Expected score of a new game: {}",
+        values[value_index::<N>((1 << amt_cells::<N>()) - 1, 0)]
+    );
+    println!("Total time: {:?}", timer.elapsed());
+}
+
+pub fn load_turn_start_values<const N: u64>() -> Vec<f32> {
+    read(turn_start_path::<N>().join("values.bin"))
+        .unwrap()
+        .chunks(4)
+        .map(|bytes| {
+            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        })
+        .collect()
+}
+
+pub fn turn_start_value<const N: u64>(
+    values: &[f32],
+    cells: &[bool],
+    points_above: u64,
+) -> f32 {
+    values[value_index::<N>(
+        cells_to_mask(cells),
+        points_above.min(bonus_threshold::<N>()),
+    )]
+}
+
+// The free cells and capped points above of a sheet, which a turn solved from
+// the turn start values depends on.
+fn state_key<const N: u64>(points: &[Option<u64>]) -> (usize, u64) {
+    let cells: Vec<_> = points.iter().map(|x| x.is_none()).collect();
+    let points_above = points
+        .iter()
+        .take(6)
+        .filter_map(|x| x.as_ref())
+        .sum::<u64>()
+        .min(bonus_threshold::<N>());
+
+    (cells_to_mask(&cells), points_above)
+}
+
+pub struct TurnStartStrategy {
+    values: Vec<f32>,
+    solution: CachedTurn<(usize, u64)>,
+}
+
+impl TurnStartStrategy {
+    pub fn load<const N: u64>() -> Self {
+        Self {
+            values: load_turn_start_values::<N>(),
+            solution: CachedTurn::new(),
+        }
+    }
+
+    fn with_solution<const N: u64, T, F: FnOnce(&TurnSolution) -> T>(
+        &self,
+        points: &[Option<u64>],
+        f: F,
+    ) -> T {
+        self.solution.with(
+            state_key::<N>(points),
+            |&(mask, points_above)| {
+                solve_turn_from_values::<N>(&self.values, mask, points_above)
+            },
+            f,
+        )
+    }
+}
+
+impl<const N: u64> Strategy<N> for TurnStartStrategy {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.rethrow::<N>(dice, throws_left).clone()
+        })
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.cell::<N>(dice)
+        })
+    }
+}
+
+/// Solves positions on demand without any tables, by recursively solving the
+/// turns of every state reachable from them. The turn start values are kept
+/// in memory, so later positions of the same game are fast. Practical up to
+/// about 8 free cells.
+#[derive(Default)]
+pub struct EndgameSolver<const N: u64> {
+    values: RefCell<HashMap<(usize, u64), f64>>,
+    solution: CachedTurn<(usize, u64)>,
+}
+
+impl<const N: u64> EndgameSolver<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expected remaining score at the start of a turn with `mask` free and
+    /// `points_above` reached.
+    pub fn turn_start_value(&self, mask: usize, points_above: u64) -> f64 {
+        if mask == 0 {
+            return 0.0;
+        }
+
+        if let Some(&value) = self.values.borrow().get(&(mask, points_above)) {
+            return value;
+        }
+
+        let solution = self.solve_turn(mask, points_above);
+        let value = DiceIter::new(N)
+            .map(|dice| dice.probability() * solution.value::<N>(&dice, 2))
+            .sum();
+
+        self.values.borrow_mut().insert((mask, points_above), value);
+
+        value
+    }
+
+    pub fn solve_turn(&self, mask: usize, points_above: u64) -> TurnSolution {
+        solve_turn::<N, _>(&mask_to_cells::<N>(mask), |cell_ind, score| {
+            let (gain, n_points_above) =
+                fill_cell::<N>(cell_ind, score, points_above);
+
+            gain as f64
+                + self.turn_start_value(mask & !(1 << cell_ind), n_points_above)
+        })
+    }
+
+    /// Amount of turn start values solved so far.
+    pub fn amt_solved(&self) -> usize {
+        self.values.borrow().len()
+    }
+
+    fn with_solution<T, F: FnOnce(&TurnSolution) -> T>(
+        &self,
+        points: &[Option<u64>],
+        f: F,
+    ) -> T {
+        self.solution.with(
+            state_key::<N>(points),
+            |&(mask, points_above)| self.solve_turn(mask, points_above),
+            f,
+        )
+    }
+}
+
+impl<const N: u64> Strategy<N> for EndgameSolver<N> {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        self.with_solution(points, |solution| {
+            solution.rethrow::<N>(dice, throws_left).clone()
+        })
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        self.with_solution(points, |solution| solution.cell::<N>(dice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        yahtzee_guide::get_total_score, yahtzee_simulation::simulate_turn,
+    };
+
+    // Ones, twos and sixes are free with 41 points above, so the bonus is
+    // often reached with sixes before the last upper cell is filled.
+    #[test]
+    fn endgame_value_matches_simulated_totals() {
+        let mut start = vec![Some(0); amt_cells::<5>()];
+        start[0] = None;
+        start[1] = None;
+        start[2] = Some(9);
+        start[3] = Some(12);
+        start[4] = Some(20);
+        start[5] = None;
+
+        let solver = EndgameSolver::<5>::new();
+        let cells: Vec<_> = start.iter().map(|x| x.is_none()).collect();
+        let expected = solver.turn_start_value(cells_to_mask(&cells), 41);
+
+        let games = 2000;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut total = 0.0;
+
+        for _ in 0..games {
+            let mut points = start.clone();
+            for _ in 0..3 {
+                simulate_turn::<5, _, _>(&solver, &mut points, &mut rng);
+            }
+            total += (get_total_score::<5>(&points)
+                - get_total_score::<5>(&start)) as f64;
+        }
+
+        let mean = total / games as f64;
+        assert!(
+            (mean - expected).abs() < 2.0,
+            "simulated {} but expected {}",
+            mean,
+            expected
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    yahtzee::DiceThrow,
+    yahtzee_distribution::{
+        is_practical, remaining_distribution, Policy, ScoreDistribution,
+    },
+    yahtzee_free_strats::amt_cells,
+    yahtzee_guide::get_total_score,
+    yahtzee_player::{load_strategy, Strategy},
+    yahtzee_simulation::simulate_turn,
+    yahtzee_target::{
+        capped_points_above, max_total, solution_key, solve_turn_from_chances,
+        SolutionKey,
+    },
+    yahtzee_turn::{CachedTurn, TurnSolution},
+    yahtzee_turn_start::{cells_to_mask, load_turn_start_values},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Goal {
+    Win,
+    Rank,
+}
+
+impl Goal {
+    pub fn parse(name: &str) -> Option<Goal> {
+        match name {
+            "win" => Some(Goal::Win),
+            "rank" => Some(Goal::Rank),
+            _ => None,
+        }
+    }
+}
+
+/// Value of each final total of ours against opponents whose final totals have
+/// the distributions `opponents`. For `Goal::Win` this is the chance of
+/// winning, with a tie shared between the tied players. For `Goal::Rank` it is
+/// one minus the expected rank, where a tie counts as half a place.
+pub fn final_utility(
+    opponents: &[&ScoreDistribution],
+    goal: Goal,
+    len: usize,
+) -> Vec<f64> {
+    (0..len)
+        .map(|score| match goal {
+            Goal::Win => {
+                // Chance of each amount of opponents tying with us, while the
+                // rest are below.
+                let mut ties = vec![1.0];
+
+                for opponent in opponents {
+                    let below = 1.0 - opponent.at_least(score);
+                    let equal = opponent.prob(score);
+
+                    let mut next = vec![0.0; ties.len() + 1];
+                    for (k, t) in ties.iter().enumerate() {
+                        next[k] += t * below;
+                        next[k + 1] += t * equal;
+                    }
+                    ties = next;
+                }
+
+                ties.iter()
+                    .enumerate()
+                    .map(|(k, t)| t / (k + 1) as f64)
+                    .sum()
+            }
+            Goal::Rank => -opponents
+                .iter()
+                .map(|o| o.at_least(score + 1) + 0.5 * o.prob(score))
+                .sum::<f64>(),
+        })
+        .collect()
+}
+
+/// Expected `utility` of the final total from a state with `total` points and
+/// `chances` of reaching each amount of points still needed.
+pub fn expected_utility(utility: &[f64], chances: &[f32], total: u64) -> f64 {
+    (0..chances.len())
+        .map(|needed| {
+            let prob = chances[needed] as f64
+                - chances.get(needed + 1).copied().unwrap_or(0.0) as f64;
+
+            prob * utility[(total as usize + needed).min(utility.len() - 1)]
+        })
+        .sum()
+}
+
+/// Chance of each player winning when all of them end with the totals in
+/// `dists`.
+pub fn win_chances(dists: &[ScoreDistribution]) -> Vec<f64> {
+    (0..dists.len())
+        .map(|j| {
+            let others: Vec<_> = dists
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != j)
+                .map(|(_, d)| d)
+                .collect();
+            let utility =
+                final_utility(&others, Goal::Win, dists[j].probs.len());
+
+            dists[j]
+                .probs
+                .iter()
+                .zip(utility.iter())
+                .map(|(p, u)| p * u)
+                .sum()
+        })
+        .collect()
+}
+
+/// Distributions of the final totals of players that play for the best
+/// expected score. The points still to get only depend on the free cells and
+/// points above, so those are kept. They count the bonus once, like the
+/// `get_total_score` that the games are judged by.
+pub struct DistributionCache {
+    values: Arc<Vec<f32>>,
+    remaining: HashMap<(usize, u64), ScoreDistribution>,
+}
+
+impl DistributionCache {
+    pub fn new(values: Arc<Vec<f32>>) -> Self {
+        Self {
+            values,
+            remaining: HashMap::new(),
+        }
+    }
+
+    pub fn get<const N: u64>(
+        &mut self,
+        points: &[Option<u64>],
+    ) -> ScoreDistribution {
+        let cells: Vec<_> = points.iter().map(|x| x.is_none()).collect();
+        let key = (cells_to_mask(&cells), capped_points_above::<N>(points));
+
+        let values = &self.values;
+        self.remaining
+            .entry(key)
+            .or_insert_with(|| {
+                remaining_distribution::<N>(
+                    &Policy::BestExpected(values.clone()),
+                    key.0,
+                    key.1,
+                    None,
+                )
+            })
+            .shifted(get_total_score::<N>(points) as usize)
+    }
+}
+
+/// Plays against opponents whose final totals have known distributions,
+/// either to maximize the chance of winning or to minimize the expected rank.
+/// The opponents are assumed to play for the best expected score, which gives
+/// the distributions from their sheets.
+pub struct VersusStrategy {
+    pub goal: Goal,
+    pub utility: Vec<f64>,
+    solution: CachedTurn<SolutionKey>,
+}
+
+impl VersusStrategy {
+    pub fn new<const N: u64>(
+        opponents: &[ScoreDistribution],
+        goal: Goal,
+    ) -> Self {
+        let opponents: Vec<_> = opponents.iter().collect();
+
+        Self {
+            goal,
+            utility: final_utility(&opponents, goal, max_total::<N>() + 1),
+            solution: CachedTurn::new(),
+        }
+    }
+
+    pub fn with_solution<const N: u64, T, F: FnOnce(&TurnSolution) -> T>(
+        &self,
+        points: &[Option<u64>],
+        f: F,
+    ) -> T {
+        self.solution.with(
+            solution_key::<N>(points),
+            |_| {
+                solve_turn_from_chances::<N, _>(points, |chances, total| {
+                    expected_utility(&self.utility, chances, total)
+                })
+            },
+            f,
+        )
+    }
+}
+
+impl<const N: u64> Strategy<N> for VersusStrategy {
+    fn rethrow(
+        &self,
+        points: &[Option<u64>],
+        dice: &DiceThrow,
+        throws_left: usize,
+    ) -> DiceThrow {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.rethrow::<N>(dice, throws_left).clone()
+        })
+    }
+
+    fn cell(&self, points: &[Option<u64>], dice: &DiceThrow) -> usize {
+        self.with_solution::<N, _, _>(points, |solution| {
+            solution.cell::<N>(dice)
+        })
+    }
+}
+
+pub enum Player<const N: u64> {
+    Fixed(Box<dyn Strategy<N>>),
+    Versus(Goal),
+}
+
+/// `win` and `rank` play against the other players, anything else is a
+/// strategy name for `load_strategy`.
+pub fn load_player<const N: u64>(name: &str) -> Result<Player<N>, String> {
+    match Goal::parse(name) {
+        Some(_) if !is_practical::<N>() => Err(format!(
+            "Playing {} needs score distributions, which are only practical \
+             for 5 dice!",
+            name
+        )),
+        Some(goal) => Ok(Player::Versus(goal)),
+        None => Ok(Player::Fixed(load_strategy::<N>(Some(name)))),
+    }
+}
+
+/// Plays `n` games where the players take turns, and shows how often each
+/// player wins, their average rank and their average total.
+pub fn simulate_multiplayer<const N: u64>(
+    names: &[&str],
+    n: usize,
+) -> Result<(), String> {
+    let players = names
+        .iter()
+        .map(|name| load_player::<N>(name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut cache = if players.iter().any(|p| matches!(p, Player::Versus(_))) {
+        Some(DistributionCache::new(Arc::new(
+            load_turn_start_values::<N>(),
+        )))
+    } else {
+        None
+    };
+
+    let mut rng = rand::thread_rng();
+
+    let mut wins = vec![0.0; players.len()];
+    let mut ranks = vec![0.0; players.len()];
+    let mut totals = vec![0.0; players.len()];
+
+    let mut timer = Instant::now();
+
+    for i in 0..n {
+        if timer.elapsed() > Duration::from_secs(1) {
+            println!("{} / {}", i, n);
+            timer += Duration::from_secs(1);
+        }
+
+        let mut sheets = vec![vec![None; amt_cells::<N>()]; players.len()];
+
+        for _ in 0..amt_cells::<N>() {
+            for (j, player) in players.iter().enumerate() {
+                match player {
+                    Player::Fixed(strategy) => simulate_turn::<N, _, _>(
+                        &**strategy,
+                        &mut sheets[j],
+                        &mut rng,
+                    ),
+                    Player::Versus(goal) => {
+                        let cache = cache.as_mut().unwrap();
+                        let opponents: Vec<_> = sheets
+                            .iter()
+                            .enumerate()
+                            .filter(|&(k, _)| k != j)
+                            .map(|(_, sheet)| cache.get::<N>(sheet))
+                            .collect();
+                        let strategy =
+                            VersusStrategy::new::<N>(&opponents, *goal);
+
+                        simulate_turn::<N, _, _>(
+                            &strategy,
+                            &mut sheets[j],
+                            &mut rng,
+                        );
+                    }
+                }
+            }
+        }
+
+        let scores: Vec<_> =
+            sheets.iter().map(|s| get_total_score::<N>(s)).collect();
+
+        for (j, &score) in scores.iter().enumerate() {
+            let above = scores.iter().filter(|&&s| s > score).count();
+            let tied = scores.iter().filter(|&&s| s == score).count() - 1;
+
+            if above == 0 {
+                wins[j] += 1.0 / (tied + 1) as f64;
+            }
+            ranks[j] += 1.0 + above as f64 + 0.5 * tied as f64;
+            totals[j] += score as f64;
+        }
+    }
+
+    for (j, name) in names.iter().enumerate() {
+        println!(
+            "player {} ({}): wins {:.4}, average rank {:.3}, average total {:.3}",
+            j + 1,
+            name,
+            wins[j] / n as f64,
+            ranks[j] / n as f64,
+            totals[j] / n as f64
+        );
+    }
+
+    Ok(())
+}